@@ -3,30 +3,50 @@
  * Created 2024-04-11
  */
 
-use std::collections::HashMap;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::PathBuf;
 
 use crossterm::event::KeyCode::Char;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::layout::Position;
 use ratatui::prelude::*;
+use ratatui::widgets::{Paragraph, Wrap};
 use regex::Regex;
 use tokio::sync::mpsc::UnboundedSender;
 
+use panes::file_tree::FileTree as FileTreePane;
 use panes::template_list::TemplateList as TemplateListPane;
 use popups::about as about_popup;
+use popups::diff::Diff as DiffPopup;
 use popups::error as error_popup;
 use popups::help as help_popup;
+use popups::save_as::SaveAs as SaveAsPopup;
+use popups::save_as::SaveAsAction;
 use popups::save_option::SaveOption as SaveOptionPopup;
 use popups::save_option::SaveOptions;
+use popups::search::Search as SearchPopup;
+use popups::search::SearchAction;
+use popups::suggest::Suggest as SuggestPopup;
+use popups::suggest::SuggestAction;
 use widgets::filter::Filter;
+use widgets::path_tester::PathTester;
 use widgets::task_action::TaskAction;
 use widgets::task_bar::TaskBar;
+use widgets::text_input::InputResult;
 
-use crate::gitignore_api;
+use crate::gitignore::annotate::{self, Annotate};
+use crate::gitignore::conflicts::{self, Conflict};
+use crate::gitignore::generated::{self, Assembled};
+use crate::gitignore::{dedupe, merge, minimize, Gitignore, Section};
+use crate::preview::Tree;
+use crate::search;
+use crate::suggest::{self, Suggestions};
 use crate::tui::event::Event;
+use crate::util;
+use crate::writer::target::{self, Target};
+use crate::writer::LineEnding;
+use crate::{audit, diff, git, gitignore_api, writer};
 
 mod list_state_wrapper;
 mod panes;
@@ -38,8 +58,12 @@ mod widgets;
 struct FrameSet {
     available: Rect,
     selected: Rect,
+    files: Rect,
     task_bar: Rect,
     filter: Rect,
+    path_tester: Rect,
+    verdict: Rect,
+    status: Rect,
 }
 
 #[derive(Copy, Clone, Default)]
@@ -53,22 +77,68 @@ enum PopupFlag {
     Help,
     About,
     SaveOption,
+    SaveAs,
+    Diff,
+    Suggest,
+    Search,
 }
 
 #[derive(Default)]
 pub(crate) struct App {
     should_quit: bool,
+    event_tx: Option<UnboundedSender<Event>>,
 
     templates: HashMap<String, FilterStatus>,
+    // Every template's rules, to show what the selection would ignore without fetching it
+    bodies: HashMap<String, String>,
+    // Whether the rules are still being fetched, and the tree of files walked
+    loading: bool,
+    walking: bool,
+    // The selected templates, in the order they go into the .gitignore file
+    selection: Vec<String>,
+    // What was searched for and the templates that have it, when the available ones are narrowed
+    // to those
+    narrowed: Option<(String, HashSet<String>)>,
 
     filter_pane: Filter,
+    path_tester: PathTester,
+    // Whether typing goes to the path tester instead of the filter
+    path_focus: bool,
+    // What the selection decides about the path being tested
+    verdict: Option<String>,
     available_pane: TemplateListPane,
     selected_pane: TemplateListPane,
+    file_tree_pane: FileTreePane,
     task_bar: TaskBar,
 
     save_option_popup: SaveOptionPopup,
+    save_as_popup: SaveAsPopup,
+    diff_popup: DiffPopup,
+    suggest_popup: SuggestPopup,
+    search_popup: SearchPopup,
 
-    save_option_flag: Option<bool>,
+    // Where the .gitignore file goes, if not the current directory
+    output: Option<PathBuf>,
+    // The directory its rules apply to
+    rules_dir: PathBuf,
+    here: bool,
+    no_backup: bool,
+    dedupe: bool,
+    minimize: bool,
+    // How many rules minimizing dropped
+    dropped: usize,
+    annotate: Option<Annotate>,
+    layout: generated::Layout,
+    // Conflicts between the selected templates, found when getting them, and the selection they
+    // were shown for when saving was held off
+    conflicts: Vec<Conflict>,
+    conflicts_shown: Option<Vec<String>>,
+    // The warning about tracked files the rules match, shown when saving was held off
+    tracked_warning: Option<String>,
+    // Patterns picked from the suggestions, which go after the templates' rules
+    patterns: Vec<String>,
+
+    save_option_flag: Option<SaveOptions>,
     popup_flag: Option<PopupFlag>,
     frame_set: FrameSet,
 }
@@ -77,6 +147,41 @@ impl App {
     pub(crate) fn set_event_tx(&mut self, event_tx: Option<UnboundedSender<Event>>) -> &mut Self {
         self.available_pane.set_event_tx(&event_tx);
         self.selected_pane.set_event_tx(&event_tx);
+        self.event_tx = event_tx;
+        self
+    }
+    pub(crate) fn set_output(&mut self, output: Option<PathBuf>) -> &mut Self {
+        self.output = output;
+        self
+    }
+    pub(crate) fn set_here(&mut self, here: bool) -> &mut Self {
+        self.here = here;
+        self
+    }
+    pub(crate) fn set_no_backup(&mut self, no_backup: bool) -> &mut Self {
+        self.no_backup = no_backup;
+        self
+    }
+    pub(crate) fn set_dedupe(&mut self, dedupe: bool) -> &mut Self {
+        self.dedupe = dedupe;
+        self
+    }
+    pub(crate) fn set_minimize(&mut self, minimize: bool) -> &mut Self {
+        self.minimize = minimize;
+        self
+    }
+    pub(crate) fn set_layout(&mut self, layout: generated::Layout) -> &mut Self {
+        self.layout = layout;
+        self
+    }
+    pub(crate) fn set_annotate(&mut self, annotate: Option<Annotate>) -> &mut Self {
+        self.annotate = annotate;
+        self
+    }
+    /// Starts by showing the suggestions to pick from.
+    pub(crate) fn set_suggestions(&mut self, suggestions: Suggestions) -> &mut Self {
+        self.suggest_popup.set_suggestions(&suggestions);
+        self.popup_flag = Some(PopupFlag::Suggest);
         self
     }
     pub(crate) fn should_quit(&self) -> bool {
@@ -89,17 +194,56 @@ impl App {
             Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event).await,
             Event::Resize(width, height) => self.handle_resize_event(width, height),
             Event::TemplateSelect(template) => self.toggle_selection(template),
+            Event::TemplateMove(template, index) => self.move_selection(template, index),
+            Event::Paste(text) => self.handle_paste_event(text),
+            Event::TreeLoaded(output_file, tree) => {
+                self.handle_tree_loaded_event(output_file, tree)
+            }
+            Event::RulesLoaded(bodies) => self.handle_rules_loaded_event(bodies),
             _ => { /* ignore */ }
         }
     }
     pub(crate) fn render(&mut self, frame: &mut Frame<'_>) {
-        self.frame_set = Self::calculate_frames(frame.size());
+        self.frame_set = Self::calculate_frames(
+            frame.size(),
+            self.verdict_height(frame.size().width),
+            self.status_height(frame.size().width),
+        );
 
         self.available_pane.render(self.frame_set.available, frame);
         self.selected_pane.render(self.frame_set.selected, frame);
+        self.file_tree_pane.render(self.frame_set.files, frame);
 
         frame.render_widget(&self.filter_pane, self.frame_set.filter);
+        frame.render_widget(&self.path_tester, self.frame_set.path_tester);
+        if let Some(verdict) = &self.verdict {
+            frame.render_widget(
+                Paragraph::new(verdict.as_str())
+                    .style(styles::STATUS_INFO_STYLE)
+                    .wrap(Wrap { trim: true }),
+                self.frame_set.verdict,
+            );
+        }
         frame.render_widget(&self.task_bar, self.frame_set.task_bar);
+        if let Some((status, style)) = self.status() {
+            frame.render_widget(
+                Paragraph::new(status)
+                    .style(style)
+                    .wrap(Wrap { trim: true }),
+                self.frame_set.status,
+            );
+        }
+
+        // The terminal cursor stays hidden unless it's placed, so it only shows in the input
+        // taking what's typed
+        if self.popup_flag.is_none() {
+            let cursor = if self.path_focus {
+                self.path_tester.cursor_position(self.frame_set.path_tester)
+            } else {
+                self.filter_pane.cursor_position(self.frame_set.filter)
+            };
+            frame.set_cursor(cursor.x, cursor.y);
+        }
 
         if let Some(popup_message) = &self.popup_flag {
             match popup_message {
@@ -107,6 +251,18 @@ impl App {
                 PopupFlag::Help => help_popup::render(frame),
                 PopupFlag::About => about_popup::render(frame),
                 PopupFlag::SaveOption => self.save_option_popup.render(frame),
+                PopupFlag::Diff => self.diff_popup.render(frame),
+                PopupFlag::Suggest => self.suggest_popup.render(frame),
+                PopupFlag::Search => {
+                    self.search_popup.render(frame);
+                    let cursor = self.search_popup.cursor_position();
+                    frame.set_cursor(cursor.x, cursor.y);
+                }
+                PopupFlag::SaveAs => {
+                    self.save_as_popup.render(frame);
+                    let cursor = self.save_as_popup.cursor_position();
+                    frame.set_cursor(cursor.x, cursor.y);
+                }
             }
         }
     }
@@ -115,6 +271,10 @@ impl App {
 impl App {
     const ABOUT_CHAR: char = 'a';
     const SAVE_CHAR: char = 's';
+    const SAVE_AS_CHAR: char = 'o';
+    const SUGGEST_CHAR: char = 'g';
+    const PATH_CHAR: char = 't';
+    const SEARCH_CHAR: char = 'f';
     const QUIT_CHAR: char = 'q';
     const NEXT_CHAR: char = 'n';
     const PREV_CHAR: char = 'p';
@@ -122,11 +282,25 @@ impl App {
     const HELP_KEY_EVENT: KeyEvent = KeyEvent::new(Self::HELP_KEY_CODE, KeyModifiers::NONE);
     const ABOUT_KEY_EVENT: KeyEvent = KeyEvent::new(Char(Self::ABOUT_CHAR), KeyModifiers::CONTROL);
     const SAVE_KEY_EVENT: KeyEvent = KeyEvent::new(Char(Self::SAVE_CHAR), KeyModifiers::CONTROL);
+    const SAVE_AS_KEY_EVENT: KeyEvent =
+        KeyEvent::new(Char(Self::SAVE_AS_CHAR), KeyModifiers::CONTROL);
+    const SEARCH_KEY_EVENT: KeyEvent =
+        KeyEvent::new(Char(Self::SEARCH_CHAR), KeyModifiers::CONTROL);
+    const SUGGEST_KEY_EVENT: KeyEvent =
+        KeyEvent::new(Char(Self::SUGGEST_CHAR), KeyModifiers::CONTROL);
     const QUIT_KEY_EVENT: KeyEvent = KeyEvent::new(Char(Self::QUIT_CHAR), KeyModifiers::CONTROL);
 
     async fn handle_init_event(&mut self) {
         self.available_pane.set_title("Available Templates");
-        self.selected_pane.set_title("Selected Templates");
+        // The API's layout sorts the templates by name, so there's no point moving them
+        if self.layout == generated::Layout::Toptal {
+            self.selected_pane
+                .set_title("Selected Templates (saved in order by name)");
+        } else {
+            self.selected_pane
+                .set_title("Selected Templates")
+                .set_reorderable(true);
+        }
         match gitignore_api::get_template_names().await {
             Ok(templates) => {
                 for template in templates {
@@ -136,15 +310,93 @@ impl App {
             }
             Err(error) => self.set_error_popup_flag(error.to_string().as_str()),
         }
+        // Getting every template's rules can take a while, so that happens in the background
+        if let Some(event_tx) = self.event_tx.clone() {
+            self.loading = true;
+            tokio::spawn(async move {
+                let bodies = gitignore_api::get_template_bodies().await;
+                let _ = event_tx.send(Event::RulesLoaded(bodies.ok()));
+            });
+        }
+        self.walk_tree();
         self.available_pane.set_focus(true);
         self.selected_pane.set_focus(false);
 
-        self.task_bar.style(styles::TASK_BAR_STYLE).buttons(vec![
+        let mut buttons = vec![
             TaskAction::new(Self::HELP_KEY_EVENT, "F1 Help"),
             TaskAction::new(Self::ABOUT_KEY_EVENT, "^A About"),
             TaskAction::new(Self::SAVE_KEY_EVENT, "^S Save"),
-            TaskAction::new(Self::QUIT_KEY_EVENT, "^Q Quit"),
-        ]);
+            TaskAction::new(Self::SAVE_AS_KEY_EVENT, "^O Save As"),
+            TaskAction::new(Self::SEARCH_KEY_EVENT, "^F Search"),
+        ];
+        if !self.suggest_popup.is_empty() {
+            buttons.push(TaskAction::new(Self::SUGGEST_KEY_EVENT, "^G Suggestions"));
+        }
+        buttons.push(TaskAction::new(Self::QUIT_KEY_EVENT, "^Q Quit"));
+        self.task_bar.style(styles::TASK_BAR_STYLE).buttons(buttons);
+    }
+    // The tree is of where the .gitignore goes, checked against what's in it now. Walking it can
+    // take a while too, so it's done in the background, with the Files pane saying so until it's
+    // done.
+    fn walk_tree(&mut self) {
+        let output_file = self.output_file();
+        self.rules_dir = git::rules_dir(&output_file);
+        let Some(event_tx) = self.event_tx.clone() else {
+            return;
+        };
+        self.walking = true;
+        self.update_files_unavailable();
+        let rules_dir = self.rules_dir.clone();
+        tokio::spawn(async move {
+            let walked = output_file.clone();
+            let tree = tokio::task::spawn_blocking(move || {
+                let existing = std::fs::read_to_string(&output_file).unwrap_or_default();
+                Tree::walk(&rules_dir, &existing)
+            })
+            .await;
+            let _ = event_tx.send(Event::TreeLoaded(walked, tree.unwrap_or_default()));
+        });
+    }
+    fn handle_tree_loaded_event(&mut self, output_file: PathBuf, tree: Tree) {
+        // A tree walked for a file that's no longer the one being saved to is out of date, and
+        // the walk for the new one is on its way
+        if output_file != self.output_file() {
+            return;
+        }
+        self.walking = false;
+        self.file_tree_pane.set_tree(tree);
+        self.update_files_unavailable();
+        self.update_preview();
+    }
+    fn handle_rules_loaded_event(&mut self, bodies: Option<Vec<(String, String)>>) {
+        self.loading = false;
+        if let Some(bodies) = bodies {
+            self.bodies = bodies.into_iter().collect();
+            self.selected_pane.set_rule_counts(self.rule_counts());
+        }
+        self.update_files_unavailable();
+        self.update_preview();
+    }
+    fn update_files_unavailable(&mut self) {
+        let reason = if self.loading || self.walking {
+            Some("loading...".to_string())
+        } else if self.bodies.is_empty() {
+            Some("couldn't get the templates' rules".to_string())
+        } else {
+            None
+        };
+        self.file_tree_pane.set_unavailable(reason);
+    }
+    // How many rules each template has, in all its sections
+    fn rule_counts(&self) -> HashMap<String, usize> {
+        self.bodies
+            .iter()
+            .map(|(template, body)| {
+                let gitignore = Gitignore::parse(body);
+                let count = gitignore.sections().iter().map(Section::rule_count).sum();
+                (template.clone(), count)
+            })
+            .collect()
     }
     fn set_templates(&mut self) {
         self.available_pane
@@ -155,7 +407,11 @@ impl App {
         self.templates
             .iter()
             .filter_map(|(template, status)| {
-                if !status.hidden && !status.selected {
+                let narrowed_out = self
+                    .narrowed
+                    .as_ref()
+                    .is_some_and(|(_, found)| !found.contains(template));
+                if !status.hidden && !status.selected && !narrowed_out {
                     Some(template.to_string())
                 } else {
                     None
@@ -164,16 +420,7 @@ impl App {
             .collect()
     }
     fn selected_templates(&self) -> Vec<String> {
-        self.templates
-            .iter()
-            .filter_map(|(template, status)| {
-                if status.selected {
-                    Some(template.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect()
+        self.selection.clone()
     }
     async fn handle_key_event(&mut self, key_event: KeyEvent) {
         // Ctrl+C closes the app, regardless of its state
@@ -193,10 +440,47 @@ impl App {
                 }
                 _ => { /* ignore other keys */ }
             }
+        } else if let Some(PopupFlag::Diff) = self.popup_flag {
+            // The diff popup goes back to the save options when it closes
+            if !self.diff_popup.handle_key_event(key_event) {
+                self.set_save_option_popup_flag();
+            }
+        } else if let Some(PopupFlag::SaveAs) = self.popup_flag {
+            // Likewise the save as popup
+            if let Some(action) = self.save_as_popup.handle_key_event(key_event) {
+                self.handle_save_as_action(action).await;
+            }
+        } else if let Some(PopupFlag::Suggest) = self.popup_flag {
+            // And the suggestions
+            if let Some(action) = self.suggest_popup.handle_key_event(key_event) {
+                self.handle_suggest_action(action);
+            }
+        } else if let Some(PopupFlag::Search) = self.popup_flag {
+            // And the search
+            if let Some(action) = self.search_popup.handle_key_event(key_event) {
+                self.handle_search_action(action);
+            }
         } else if !self.maybe_clear_message() {
             // If there's another popup, any key event will clear it and stop processing the event
+            let control = key_event.modifiers == KeyModifiers::CONTROL;
+            let plain = !key_event
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
             match key_event.code {
                 KeyCode::Tab => self.toggle_focus(),
+                // A space is part of a path, not a selection
+                Char(' ') if self.path_focus => self.path_tester_handle_key_event(key_event),
+                // Home and End move the cursor in an input with something typed in it
+                KeyCode::Home | KeyCode::End
+                    if plain && self.path_focus && !self.path_tester.text().is_empty() =>
+                {
+                    self.path_tester_handle_key_event(key_event)
+                }
+                KeyCode::Home | KeyCode::End
+                    if plain && !self.path_focus && !self.filter_pane.text().is_empty() =>
+                {
+                    self.filter_pane.handle_key_event(key_event);
+                }
                 KeyCode::Enter
                 | KeyCode::Home
                 | KeyCode::PageUp
@@ -204,80 +488,151 @@ impl App {
                 | KeyCode::End
                 | KeyCode::PageDown
                 | KeyCode::Down
-                | Char(' ') => self.pane_handle_key_event(key_event),
-                KeyCode::Esc | KeyCode::Delete => {
-                    self.filter_pane.clear();
-                    self.apply_filter();
+                | Char(' ')
+                    if plain =>
+                {
+                    self.pane_handle_key_event(key_event)
+                }
+                KeyCode::Up | KeyCode::Down
+                    if key_event.modifiers == KeyModifiers::ALT
+                        && self.selected_pane.has_focus() =>
+                {
+                    self.selected_pane.handle_key_event(key_event)
                 }
-                KeyCode::Backspace => {
-                    self.filter_pane.pop();
+                KeyCode::Esc if self.path_focus => {
+                    self.path_tester.commit();
+                    self.path_tester.clear();
+                    self.update_verdict();
+                }
+                KeyCode::Esc => {
+                    self.filter_pane.commit();
+                    self.filter_pane.clear();
+                    self.set_narrowed(None);
                     self.apply_filter();
                 }
                 Self::HELP_KEY_CODE => self.set_help_popup_flag(),
-                Char(ch) => {
-                    if key_event.modifiers == KeyModifiers::CONTROL {
-                        match ch {
-                            Self::QUIT_CHAR => self.quit(),
-                            Self::ABOUT_CHAR => self.set_about_popup_flag(),
-                            Self::SAVE_CHAR => self.save().await,
-                            Self::PREV_CHAR => self.pane_handle_key_event(KeyEvent::new(
-                                KeyCode::Up,
-                                KeyModifiers::NONE,
-                            )),
-                            Self::NEXT_CHAR => self.pane_handle_key_event(KeyEvent::new(
-                                KeyCode::Down,
-                                KeyModifiers::NONE,
-                            )),
-                            _ => { /* ignore other control chars */ }
-                        }
-                    } else {
-                        self.filter_pane.push(ch.to_ascii_lowercase());
+                Char(Self::QUIT_CHAR) if control => self.quit(),
+                Char(Self::ABOUT_CHAR) if control => self.set_about_popup_flag(),
+                Char(Self::SAVE_CHAR) if control => self.save().await,
+                Char(Self::SAVE_AS_CHAR) if control => self.set_save_as_popup_flag(),
+                Char(Self::SUGGEST_CHAR) if control && !self.suggest_popup.is_empty() => {
+                    self.popup_flag = Some(PopupFlag::Suggest)
+                }
+                Char(Self::PATH_CHAR) if control => self.toggle_input_focus(),
+                Char(Self::SEARCH_CHAR) if control => self.set_search_popup_flag(),
+                Char(Self::PREV_CHAR) if control => {
+                    self.pane_handle_key_event(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE))
+                }
+                Char(Self::NEXT_CHAR) if control => {
+                    self.pane_handle_key_event(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE))
+                }
+                // Anything else is for the input taking what's typed, which ignores what it doesn't
+                // understand
+                _ if self.path_focus => self.path_tester_handle_key_event(key_event),
+                _ => {
+                    if self.filter_pane.handle_key_event(key_event) == InputResult::Changed {
                         self.apply_filter();
                     }
                 }
-                _ => { /* ignore other keys */ }
+            }
+        }
+    }
+    fn path_tester_handle_key_event(&mut self, key_event: KeyEvent) {
+        if self.path_tester.handle_key_event(key_event) == InputResult::Changed {
+            self.update_verdict();
+        }
+    }
+    // Pasted text that names known templates selects them, and anything else goes into the filter.
+    fn handle_paste_event(&mut self, text: String) {
+        if let Some(PopupFlag::SaveAs) = self.popup_flag {
+            self.save_as_popup.paste(text.as_str());
+            return;
+        }
+        if let Some(PopupFlag::Search) = self.popup_flag {
+            let action = self.search_popup.paste(text.as_str());
+            self.handle_search_action(action);
+            return;
+        }
+        if self.popup_flag.is_some() {
+            return;
+        }
+        if self.path_focus {
+            self.path_tester.paste(text.as_str());
+            self.update_verdict();
+            return;
+        }
+        let (known, unknown): (Vec<String>, Vec<String>) = util::split_template_list(&text)
+            .into_iter()
+            .partition(|name| self.templates.contains_key(name));
+        if known.is_empty() {
+            self.filter_pane.paste(text.as_str());
+            self.apply_filter();
+        } else {
+            self.select_templates(known);
+            if !unknown.is_empty() {
+                self.set_error_popup_flag(
+                    format!("Unknown templates: {}", unknown.join(", ")).as_str(),
+                );
             }
         }
     }
     fn pane_handle_key_event(&mut self, key_event: KeyEvent) {
         if self.available_pane.has_focus() {
             self.available_pane.handle_key_event(key_event);
-        } else {
+        } else if self.selected_pane.has_focus() {
             self.selected_pane.handle_key_event(key_event);
+        } else {
+            self.file_tree_pane.handle_key_event(key_event);
         }
     }
     async fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
         // If the save option popup is active, it handles all mouse events
         if let Some(PopupFlag::SaveOption) = self.popup_flag {
             match mouse_event.kind {
-                MouseEventKind::Up(mouse_button) => {
-                    if mouse_button == MouseButton::Left {
-                        match self
-                            .save_option_popup
-                            .hit_test(mouse_event.column, mouse_event.row)
-                        {
-                            None => { /* ignore random clicks */ }
-                            Some(save_option) => self.handle_save_option(save_option).await,
-                        }
+                MouseEventKind::Up(MouseButton::Left) => {
+                    match self
+                        .save_option_popup
+                        .hit_test(mouse_event.column, mouse_event.row)
+                    {
+                        None => { /* ignore random clicks */ }
+                        Some(save_option) => self.handle_save_option(save_option).await,
                     }
                 }
                 _ => { /* ignore other events */ }
             }
+        } else if let Some(PopupFlag::Diff) = self.popup_flag {
+            self.diff_popup.handle_mouse_event(mouse_event);
+        } else if let Some(PopupFlag::SaveAs) = self.popup_flag {
+            if let Some(action) = self.save_as_popup.handle_mouse_event(mouse_event) {
+                self.handle_save_as_action(action).await;
+            }
+        } else if let Some(PopupFlag::Suggest) = self.popup_flag {
+            self.suggest_popup.handle_mouse_event(mouse_event);
+        } else if let Some(PopupFlag::Search) = self.popup_flag {
+            self.search_popup.handle_mouse_event(mouse_event);
         } else {
             match mouse_event.kind {
                 MouseEventKind::Up(mouse_button) => {
                     if mouse_button == MouseButton::Left && !self.maybe_clear_message() {
                         // If there's another popup, a mouse up event might close it and stop processing the event.
-                        if self
-                            .available_pane
-                            .hit_test(mouse_event.column, mouse_event.row)
+                        let (x, y) = (mouse_event.column, mouse_event.row);
+                        let position = Position::new(x, y);
+                        if self.frame_set.filter.contains(position) {
+                            self.set_input_focus(false);
+                        } else if self.frame_set.path_tester.contains(position) {
+                            self.set_input_focus(true);
+                        } else if self.available_pane.hit_test(x, y)
                             && !self.available_pane.has_focus()
-                            || self
-                                .selected_pane
-                                .hit_test(mouse_event.column, mouse_event.row)
-                                && !self.selected_pane.has_focus()
                         {
-                            self.toggle_focus();
+                            self.focus_available();
+                        } else if self.selected_pane.hit_test(x, y)
+                            && !self.selected_pane.has_focus()
+                        {
+                            self.focus_selected();
+                        } else if self.file_tree_pane.hit_test(x, y)
+                            && !self.file_tree_pane.has_focus()
+                        {
+                            self.focus_files();
                         } else {
                             self.pane_handle_mouse_event(mouse_event).await;
                         }
@@ -294,12 +649,10 @@ impl App {
             .contains(Position::new(mouse_event.column, mouse_event.row))
         {
             match mouse_event.kind {
-                MouseEventKind::Up(mouse_button) => {
-                    if mouse_button == MouseButton::Left {
-                        // Task bar click may be translated into a key press
-                        if let Some(key_event) = self.task_bar.hit_test(mouse_event.column) {
-                            self.handle_key_event(key_event).await;
-                        }
+                MouseEventKind::Up(MouseButton::Left) => {
+                    // Task bar click may be translated into a key press
+                    if let Some(key_event) = self.task_bar.hit_test(mouse_event.column) {
+                        self.handle_key_event(key_event).await;
                     }
                 }
                 _ => { /* ignore other events */ }
@@ -308,34 +661,86 @@ impl App {
             self.available_pane.handle_mouse_event(mouse_event);
         } else if self.selected_pane.has_focus() {
             self.selected_pane.handle_mouse_event(mouse_event);
+        } else if self.file_tree_pane.has_focus() {
+            self.file_tree_pane.handle_mouse_event(mouse_event);
         }
     }
     fn handle_resize_event(&mut self, width: u16, height: u16) {
-        self.frame_set = Self::calculate_frames(Rect::new(0, 0, width, height));
+        self.frame_set = Self::calculate_frames(
+            Rect::new(0, 0, width, height),
+            self.verdict_height(width),
+            self.status_height(width),
+        );
         self.available_pane
             .handle_resize_event(self.frame_set.available);
         self.selected_pane
             .handle_resize_event(self.frame_set.selected);
+        self.file_tree_pane
+            .handle_resize_event(self.frame_set.files);
     }
     fn quit(&mut self) {
         self.should_quit = true;
     }
     async fn handle_save_option(&mut self, save_option: SaveOptions) {
         match save_option {
-            SaveOptions::Replace => self.overwrite_save().await,
-            SaveOptions::Append => self.append_save().await,
+            SaveOptions::Replace | SaveOptions::Append | SaveOptions::Merge => {
+                self.save_with_option(save_option).await
+            }
+            SaveOptions::ShowDiff => self.show_diff().await,
             SaveOptions::Cancel => self.cancel_save(),
         }
     }
-    async fn overwrite_save(&mut self) {
-        // Cancel the save option popup and call save again, this time with the overwrite flag set
-        self.save_option_flag = Some(true);
+    async fn handle_save_as_action(&mut self, action: SaveAsAction) {
         self.popup_flag = None;
-        self.save().await;
+        if let SaveAsAction::Save(path) = action {
+            let output_file = self.output_file();
+            self.output = Some(path);
+            // The Files pane and the path tester go by where the rules are saved
+            if self.output_file() != output_file {
+                self.walk_tree();
+                self.update_preview();
+            }
+            self.save().await;
+        }
     }
-    async fn append_save(&mut self) {
-        // Cancel the save option popup and call save again, this time with the overwrite flag clear
-        self.save_option_flag = Some(false);
+    fn handle_suggest_action(&mut self, action: SuggestAction) {
+        self.popup_flag = None;
+        if let SuggestAction::Use(templates, patterns) = action {
+            // Only templates the app knows about can be selected
+            let templates: Vec<String> = templates
+                .into_iter()
+                .filter(|template| self.templates.contains_key(template))
+                .collect();
+            self.patterns = patterns;
+            self.select_templates(templates);
+        }
+    }
+    fn handle_search_action(&mut self, action: SearchAction) {
+        match action {
+            SearchAction::Search(query) => {
+                let found = search::search(&self.bodies, &query);
+                self.search_popup.set_found(found);
+            }
+            SearchAction::Narrow(query, templates) => {
+                self.popup_flag = None;
+                self.set_narrowed(Some((query, templates.into_iter().collect())));
+            }
+            SearchAction::Close => self.popup_flag = None,
+        }
+    }
+    // Shows only the available templates that have what was searched for, or all of them again.
+    fn set_narrowed(&mut self, narrowed: Option<(String, HashSet<String>)>) {
+        let title = match &narrowed {
+            Some((query, _)) => format!(r#"Available Templates containing "{}""#, query),
+            None => "Available Templates".to_string(),
+        };
+        self.available_pane.set_title(&title);
+        self.narrowed = narrowed;
+        self.set_templates();
+    }
+    async fn save_with_option(&mut self, save_option: SaveOptions) {
+        // Cancel the save option popup and call save again, this time with the save option set
+        self.save_option_flag = Some(save_option);
         self.popup_flag = None;
         self.save().await;
     }
@@ -343,50 +748,49 @@ impl App {
         // Just cancel the save option popup
         self.popup_flag = None;
     }
-    async fn save(&mut self) {
+    async fn show_diff(&mut self) {
+        let output_file = self.output_file();
+        let Some((result, _)) = self.get_gitignore().await else {
+            return;
+        };
+        match std::fs::read_to_string(&output_file) {
+            Ok(existing) => {
+                let title = format!("{} vs. selected templates", output_file.display());
+                self.diff_popup
+                    .set_diff(title.as_str(), diff::diff_lines(&existing, &result));
+                self.popup_flag = Some(PopupFlag::Diff);
+            }
+            Err(error) => self.set_error_popup_flag(error.to_string().as_str()),
+        }
+    }
+    // Gets the .gitignore content for the selected templates and any patterns picked from the
+    // suggestions, and which lines each template's rules are on, showing an error if any can't be
+    // fetched.
+    async fn get_gitignore(&mut self) -> Option<(String, Vec<(String, Range<usize>)>)> {
         let selected_templates = self.selected_templates();
-        if selected_templates.is_empty() {
+        if selected_templates.is_empty() && self.patterns.is_empty() {
             self.set_error_popup_flag("Select one or more templates and try again.");
+            return None;
+        }
+        let mut assembled = if selected_templates.is_empty() {
+            Assembled {
+                content: String::new(),
+                templates: vec![],
+            }
         } else {
-            match gitignore_api::get_template(&selected_templates).await {
-                Ok(result) => {
-                    let output_file = Path::new("./.gitignore");
-                    let mut open_options = OpenOptions::new();
-                    let message: &str;
-                    if output_file.exists() {
-                        match self.save_option_flag {
-                            Some(overwrite_flag) => {
-                                if overwrite_flag {
-                                    self.save_option_flag = None;
-                                    open_options.truncate(true).write(true);
-                                    message = "Replaced contents of existing .gitignore file.";
-                                } else {
-                                    self.save_option_flag = None;
-                                    open_options.append(true);
-                                    message = "Appended templates to existing .gitignore file.";
-                                }
-                            }
-                            None => {
-                                self.set_save_option_popup_flag();
-                                return;
-                            }
-                        }
-                    } else {
-                        open_options.create(true).write(true);
-                        message = "Created new .gitingore file.";
-                    }
-                    match open_options.open(output_file) {
-                        Ok(mut file) => {
-                            if let Err(error) = file.write(result.as_bytes()) {
-                                self.set_error_popup_flag(error.to_string().as_str())
-                            } else {
-                                self.quit();
-                                print!("[{}] {}", env!("CARGO_PKG_NAME"), message);
-                            }
-                        }
-                        Err(error) => self.set_error_popup_flag(error.to_string().as_str()),
-                    }
+            match gitignore_api::get_gitignore(&selected_templates, self.layout).await {
+                Ok(fetched) if !fetched.failed.is_empty() => {
+                    let failed: Vec<String> = fetched
+                        .failed
+                        .iter()
+                        .map(|(name, error)| format!("{}: {}", name, error))
+                        .collect();
+                    self.set_error_popup_flag(
+                        format!("Problem getting templates:\n{}", failed.join("\n")).as_str(),
+                    );
+                    return None;
                 }
+                Ok(fetched) => fetched.assembled,
                 Err(error) => {
                     self.set_error_popup_flag(
                         format!(
@@ -396,8 +800,122 @@ impl App {
                         )
                         .as_str(),
                     );
+                    return None;
+                }
+            }
+        };
+        suggest::append_patterns(&mut assembled, &self.patterns);
+        let (mut result, mut templates) = (assembled.content, assembled.templates);
+        self.conflicts = conflicts::conflicts(&result, &templates);
+        self.dropped = 0;
+        if self.minimize {
+            let dropped;
+            (result, dropped) = minimize::minimize(&result);
+            minimize::remap(&mut templates, &dropped);
+            self.dropped = dropped.len();
+        }
+        if self.dedupe {
+            result = dedupe::dedupe(&result);
+        }
+        if self.annotate == Some(Annotate::Comments) {
+            result = annotate::with_comments(&result, &templates);
+        }
+        Some((result, templates))
+    }
+    async fn save(&mut self) {
+        let Some((result, templates)) = self.get_gitignore().await else {
+            return;
+        };
+        // Conflicts show on the status line first, and saving again goes ahead anyway
+        let selection = self.selected_templates();
+        if !self.conflicts.is_empty() && self.conflicts_shown.as_ref() != Some(&selection) {
+            self.conflicts_shown = Some(selection);
+            return;
+        }
+        let target = self.target();
+        let output_file = target.path;
+        let content: String;
+        let mut message: String;
+        if output_file.exists() {
+            let Some(save_option) = self.save_option_flag.take() else {
+                self.save_option_popup.set_path(&output_file);
+                self.set_save_option_popup_flag();
+                return;
+            };
+            let existing = match std::fs::read_to_string(&output_file) {
+                Ok(existing) => existing,
+                Err(error) => {
+                    self.set_error_popup_flag(error.to_string().as_str());
+                    return;
                 }
             };
+            // Whatever happens to the file, it keeps its line endings
+            let line_ending = LineEnding::detect(&existing).unwrap_or_default();
+            match save_option {
+                SaveOptions::Append => {
+                    let comment = if selection.is_empty() {
+                        format!("Added by {} from suggestions", env!("CARGO_PKG_NAME"))
+                    } else {
+                        format!(
+                            "Added by {} from templates: {}",
+                            env!("CARGO_PKG_NAME"),
+                            selection.join(", ")
+                        )
+                    };
+                    content = writer::append(&existing, &result, &comment);
+                    message = format!("Appended templates to {}.", output_file.display());
+                }
+                SaveOptions::Merge => {
+                    content = line_ending.normalize(&merge::merge(&existing, &result));
+                    message = format!("Merged new rules into {}.", output_file.display());
+                }
+                _ => {
+                    content = line_ending.normalize(&result);
+                    message = format!("Replaced contents of {}.", output_file.display());
+                }
+            }
+        } else {
+            content = LineEnding::default().normalize(&result);
+            message = format!("Created {}.", output_file.display());
+        }
+        if self.dropped > 0 {
+            message = format!(
+                "{}\nDropped {} rule(s) that couldn't change what's ignored.",
+                message, self.dropped
+            );
+        }
+        if let Some(warning) = target.warning {
+            message = format!("{}\nWarning: {}", message, warning);
+        }
+        // Tracked files that the rules match show on the status line before anything is
+        // written, and saving again goes ahead anyway
+        let tracked_warning = audit::warning(&output_file, &content);
+        if tracked_warning.is_some() && self.tracked_warning != tracked_warning {
+            self.tracked_warning = tracked_warning;
+            return;
+        }
+        if let Some(warning) = tracked_warning {
+            message = format!("{}\nWarning: {}", message, warning);
+        }
+        match writer::save(&output_file, &content, !self.no_backup) {
+            Ok(()) => {
+                if self.annotate == Some(Annotate::Json) {
+                    let sidecar_path = annotate::sidecar_path(&output_file);
+                    let sidecar = annotate::sidecar(&result, &templates);
+                    message = match writer::save(&sidecar_path, &sidecar, false) {
+                        Ok(()) => format!("{}\nWrote {}.", message, sidecar_path.display()),
+                        Err(error) => format!(
+                            "{}\nWarning: Couldn't write {}: {}",
+                            message,
+                            sidecar_path.display(),
+                            error
+                        ),
+                    };
+                }
+                self.quit();
+                print!("[{}] {}", env!("CARGO_PKG_NAME"), message);
+            }
+            Err(error) => self.set_error_popup_flag(error.to_string().as_str()),
         }
     }
     fn set_error_popup_flag(&mut self, message: &str) {
@@ -412,6 +930,25 @@ impl App {
     fn set_save_option_popup_flag(&mut self) {
         self.popup_flag = Some(PopupFlag::SaveOption);
     }
+    fn set_search_popup_flag(&mut self) {
+        if self.loading {
+            self.set_error_popup_flag("Still getting the templates' rules to search.");
+        } else if self.bodies.is_empty() {
+            self.set_error_popup_flag("Couldn't get the templates' rules to search.");
+        } else {
+            self.popup_flag = Some(PopupFlag::Search);
+        }
+    }
+    fn set_save_as_popup_flag(&mut self) {
+        self.save_as_popup.open(&self.output_file());
+        self.popup_flag = Some(PopupFlag::SaveAs);
+    }
+    fn output_file(&self) -> PathBuf {
+        self.target().path
+    }
+    fn target(&self) -> Target {
+        target::resolve(self.output.as_deref(), self.here)
+    }
     // Clears the error, help or about popup if they are showing, and returns
     // true to indicate that the event was handled. Does nothing if the
     // save option popup is showing or if no popup is showing, returning false
@@ -423,22 +960,114 @@ impl App {
                     self.popup_flag = None;
                     true
                 }
-                PopupFlag::SaveOption => false,
+                PopupFlag::SaveOption
+                | PopupFlag::SaveAs
+                | PopupFlag::Diff
+                | PopupFlag::Suggest
+                | PopupFlag::Search => false,
             };
         }
         false
     }
     // Finds a template by name and toggles its selected flag.
     fn toggle_selection(&mut self, template: String) {
-        let filter_status = self.templates.entry(template).or_default();
+        self.filter_pane.commit();
+        let filter_status = self.templates.entry(template.to_string()).or_default();
         filter_status.selected = !filter_status.selected;
+        if filter_status.selected {
+            self.selection.push(template);
+        } else {
+            self.selection.retain(|t| *t != template);
+        }
+        self.conflicts.clear();
+        self.conflicts_shown = None;
+        self.tracked_warning = None;
         self.set_templates();
+        self.update_preview();
+    }
+    // Selects all the named templates at once, leaving any that are already selected alone.
+    fn select_templates(&mut self, templates: Vec<String>) {
+        for template in templates {
+            let filter_status = self.templates.entry(template.to_string()).or_default();
+            if !filter_status.selected {
+                filter_status.selected = true;
+                self.selection.push(template);
+            }
+        }
+        self.conflicts.clear();
+        self.conflicts_shown = None;
+        self.tracked_warning = None;
+        self.set_templates();
+        self.update_preview();
+    }
+    // Moves a selected template to a new position in the selection.
+    fn move_selection(&mut self, template: String, index: usize) {
+        if let Some(current) = self.selection.iter().position(|t| *t == template) {
+            let template = self.selection.remove(current);
+            self.selection
+                .insert(index.min(self.selection.len()), template);
+            self.conflicts.clear();
+            self.conflicts_shown = None;
+            self.tracked_warning = None;
+            self.set_templates();
+            self.update_preview();
+        }
+    }
+    // Shows what the selected templates and picked patterns would ignore, from the rules fetched
+    // at the start.
+    fn update_preview(&mut self) {
+        let assembled = self.preview_rules();
+        self.file_tree_pane.update(&assembled.content);
+        self.update_verdict();
+    }
+    fn update_verdict(&mut self) {
+        let path = self.path_tester.text().trim();
+        self.verdict = if self.loading && !path.is_empty() {
+            Some(format!(
+                "{}: can't tell yet, still getting the templates' rules",
+                path
+            ))
+        } else if self.bodies.is_empty() && !path.is_empty() {
+            Some(format!(
+                "{}: can't tell, couldn't get the templates' rules",
+                path
+            ))
+        } else {
+            self.path_tester
+                .verdict(&self.preview_rules(), &self.rules_dir)
+        };
+    }
+    // The selected templates' rules one after another, and any picked patterns
+    fn preview_rules(&self) -> Assembled {
+        let mut assembled = Assembled {
+            content: String::new(),
+            templates: vec![],
+        };
+        for template in &self.selection {
+            if let Some(body) = self.bodies.get(template) {
+                if !assembled.content.is_empty() {
+                    assembled.content.push('\n');
+                }
+                let start = assembled.content.matches('\n').count();
+                assembled.content.push_str(body);
+                let end = start + body.matches('\n').count() + 1;
+                assembled.templates.push((template.clone(), start..end));
+            }
+        }
+        suggest::append_patterns(&mut assembled, &self.patterns);
+        assembled
     }
     fn apply_filter(&mut self) {
         // We escape the filter so we can use the input as a regular expression, and we also ignore
         // any filter that can't be used as a regular expression. (Belt and suspenders!) This is
-        // really only relevant for "c++".
-        let filter = regex::escape(self.filter_pane.text());
+        // really only relevant for "c++". A comma-separated filter matches any of its terms.
+        let filter = self
+            .filter_pane
+            .terms()
+            .iter()
+            .map(|term| regex::escape(term))
+            .collect::<Vec<String>>()
+            .join("|");
         if let Ok(regex) = Regex::new(filter.as_str()) {
             let templates: Vec<String> = self.templates.keys().map(|t| t.to_string()).collect();
             for template in templates {
@@ -448,52 +1077,133 @@ impl App {
             self.set_templates();
         }
     }
+    // Moves the focus on to the next pane, from the available templates to the selected ones to
+    // the files and back.
     fn toggle_focus(&mut self) {
         if self.available_pane.has_focus() {
             self.focus_selected();
+        } else if self.selected_pane.has_focus() {
+            self.focus_files();
         } else {
             self.focus_available();
         }
     }
-    fn focus_available(&mut self) {
-        if !self.available_pane.has_focus() {
-            self.available_pane.set_focus(true);
-            self.selected_pane.set_focus(false);
+    // Moves typing between the filter and the path tester.
+    fn toggle_input_focus(&mut self) {
+        self.set_input_focus(!self.path_focus);
+    }
+    fn set_input_focus(&mut self, path_focus: bool) {
+        if self.path_focus == path_focus {
+            return;
+        }
+        if path_focus {
+            self.filter_pane.commit();
+        } else {
+            self.path_tester.commit();
         }
+        self.path_focus = path_focus;
+    }
+    fn focus_available(&mut self) {
+        self.available_pane.set_focus(true);
+        self.selected_pane.set_focus(false);
+        self.file_tree_pane.set_focus(false);
     }
     fn focus_selected(&mut self) {
-        if !self.selected_pane.has_focus() {
-            self.available_pane.set_focus(false);
-            self.selected_pane.set_focus(true);
-        }
+        self.available_pane.set_focus(false);
+        self.selected_pane.set_focus(true);
+        self.file_tree_pane.set_focus(false);
+    }
+    fn focus_files(&mut self) {
+        self.available_pane.set_focus(false);
+        self.selected_pane.set_focus(false);
+        self.file_tree_pane.set_focus(true);
     }
 }
 
 impl App {
-    fn calculate_frames(rect: Rect) -> FrameSet {
+    // What goes on the status line, if anything: conflicts or tracked files that held off
+    // saving, or else the patterns picked from the suggestions
+    fn status(&self) -> Option<(String, Style)> {
+        if let Some(conflict) = self.conflicts.first() {
+            let status = format!(
+                "{} conflict(s) between templates: {}. Save again to go ahead.",
+                self.conflicts.len(),
+                conflict.message
+            );
+            Some((status, styles::STATUS_WARNING_STYLE))
+        } else if let Some(warning) = &self.tracked_warning {
+            let status = format!("{} Save again to go ahead.", warning);
+            Some((status, styles::STATUS_WARNING_STYLE))
+        } else if !self.patterns.is_empty() {
+            let status = format!(
+                "Adding {} pattern(s) from the suggestions: {}",
+                self.patterns.len(),
+                self.patterns.join(", ")
+            );
+            Some((status, styles::STATUS_INFO_STYLE))
+        } else {
+            None
+        }
+    }
+    // A long status wraps onto a second line, like the verdict
+    fn status_height(&self, width: u16) -> u16 {
+        match self.status() {
+            Some((status, _)) if status.chars().count() > width as usize => 2,
+            Some(_) => 1,
+            None => 0,
+        }
+    }
+    // A long verdict wraps onto a second line
+    fn verdict_height(&self, width: u16) -> u16 {
+        match &self.verdict {
+            Some(verdict) if verdict.chars().count() > width as usize => 2,
+            Some(_) => 1,
+            None => 0,
+        }
+    }
+    fn calculate_frames(rect: Rect, verdict: u16, status: u16) -> FrameSet {
         let root = Layout::default()
             .constraints([
                 Constraint::Length(1),
+                Constraint::Length(verdict),
                 Constraint::Min(1),
+                Constraint::Length(status),
                 Constraint::Length(1),
             ])
             .split(rect);
         let top = root[0];
-        let middle = root[1];
-        let bottom = root[2];
+        let verdict = root[1];
+        let middle = root[2];
+        let status = root[3];
+        let bottom = root[4];
+
+        // The path tester lines up with the Files pane
+        let inputs = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(top);
 
         let main = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+            ])
             .split(middle);
         let left = main[0];
         let right = main[1];
+        let files = main[2];
 
         FrameSet {
-            filter: top,
+            filter: inputs[0],
+            path_tester: inputs[1],
+            verdict,
             available: left,
             selected: right,
+            files,
             task_bar: bottom,
+            status,
         }
     }
 }
@@ -3,4 +3,5 @@
  * Created 2024-04-14
  */
 
+pub(super) mod file_tree;
 pub(super) mod template_list;
@@ -0,0 +1,150 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-05
+ */
+
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use ratatui::style::Modifier;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem};
+use ratatui::Frame;
+
+use crate::app::list_state_wrapper::ListStateWrapper;
+use crate::app::panes::template_list::TemplateList;
+use crate::app::styles;
+use crate::preview::{Preview, Tree};
+use crate::util;
+
+/// The files under where the .gitignore goes, with what the selection would ignore dimmed, and
+/// struck through if it isn't ignored now
+#[derive(Default)]
+pub(crate) struct FileTree {
+    has_focus: bool,
+    area: Rect,
+
+    tree: Tree,
+    preview: Preview,
+    // Why there's nothing to preview, if there isn't
+    unavailable: Option<String>,
+    list_state: ListStateWrapper,
+}
+
+impl FileTree {
+    pub(crate) fn has_focus(&self) -> bool {
+        self.has_focus
+    }
+    pub(crate) fn set_focus(&mut self, focus: bool) {
+        self.has_focus = focus;
+    }
+    pub(crate) fn hit_test(&self, x: u16, y: u16) -> bool {
+        self.area.contains(Position::new(x, y))
+    }
+    pub(crate) fn set_tree(&mut self, tree: Tree) -> &mut Self {
+        self.tree = tree;
+        self
+    }
+    pub(crate) fn set_unavailable(&mut self, reason: Option<String>) -> &mut Self {
+        self.unavailable = reason;
+        self
+    }
+    /// Shows what `content` would ignore.
+    pub(crate) fn update(&mut self, content: &str) {
+        self.preview = self.tree.preview(content);
+        self.list_state.set_size(self.preview.rows.len());
+        match self.list_state.selected() {
+            Some(selected) if selected > self.list_state.upper_bound => {
+                self.list_state.select_last()
+            }
+            Some(_) => {}
+            None => self.list_state.select_first(),
+        }
+    }
+    pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Home => self.list_state.select_first(),
+            KeyCode::PageUp => self.list_state.recede_selected(self.page_size()),
+            KeyCode::Up => self.list_state.recede_selected(1),
+            KeyCode::End => self.list_state.select_last(),
+            KeyCode::PageDown => self.list_state.advance_selected(self.page_size()),
+            KeyCode::Down => self.list_state.advance_selected(1),
+            _ => { /* ignore other keys */ }
+        }
+    }
+    pub(crate) fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp => self.list_state.recede_selected(1),
+            MouseEventKind::ScrollDown => self.list_state.advance_selected(1),
+            _ => { /* ignore other events */ }
+        }
+    }
+    pub(crate) fn handle_resize_event(&mut self, area: Rect) {
+        self.area = area;
+    }
+    pub(crate) fn render(&mut self, area: Rect, frame: &mut Frame<'_>) {
+        self.area = area;
+
+        let block = if self.has_focus {
+            TemplateList::focused_block()
+        } else {
+            TemplateList::default_block()
+        }
+        .title(self.title());
+        let items: Vec<ListItem> = self
+            .preview
+            .rows
+            .iter()
+            .map(|row| {
+                let entry = &self.tree.entries[row.index];
+                let mut text = entry.name.clone();
+                if entry.is_dir {
+                    text.push('/');
+                    // What's in an ignored directory isn't shown, so say how much there is
+                    if row.ignored && !entry.ignored {
+                        text.push_str(&format!(
+                            " ({} file(s), {})",
+                            entry.files,
+                            util::format_size(entry.bytes)
+                        ));
+                    }
+                }
+                let mut style = styles::DEFAULT_TITLE_STYLE;
+                if row.ignored {
+                    style = style.add_modifier(Modifier::DIM);
+                }
+                if row.newly {
+                    style = style.add_modifier(Modifier::CROSSED_OUT);
+                }
+                // Only the name is struck through, not the indent or the rest of the row
+                let indent = Span::styled("  ".repeat(entry.depth), styles::DEFAULT_TITLE_STYLE);
+                ListItem::new(Line::from(vec![indent, Span::styled(text, style)]))
+            })
+            .collect();
+        let mut list = List::new(items).block(block);
+        if self.has_focus {
+            list = list.highlight_style(styles::LIST_HIGHLIGHT_STYLE);
+        }
+        frame.render_stateful_widget(list, area, &mut self.list_state.list_state)
+    }
+}
+impl FileTree {
+    // Like "Files: 12 newly ignored (3.4 MB)"
+    fn title(&self) -> String {
+        let mut title = match &self.unavailable {
+            Some(reason) => format!("Files ({})", reason),
+            None if self.preview.files == 0 => "Files: none newly ignored".to_string(),
+            None => format!(
+                "Files: {} newly ignored ({})",
+                self.preview.files,
+                util::format_size(self.preview.bytes)
+            ),
+        };
+        if self.tree.truncated {
+            title.push_str(", not all shown");
+        }
+        title
+    }
+    fn page_size(&self) -> usize {
+        self.area.height.saturating_sub(2) as usize
+    }
+}
@@ -3,6 +3,8 @@
  * Created 2024-04-14
  */
 
+use std::collections::HashMap;
+
 use crossterm::event::KeyCode::Char;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::layout::{Position, Rect};
@@ -24,6 +26,13 @@ pub(crate) struct TemplateList {
 
     templates: Vec<String>,
     list_state: ListStateWrapper,
+    // How many rules each template has, shown beside its name once they're known
+    rule_counts: HashMap<String, usize>,
+
+    // A reorderable list keeps its templates in the order it's given, and the user can move them
+    reorderable: bool,
+    drag_index: Option<usize>,
+    dragged: bool,
 }
 
 impl TemplateList {
@@ -43,12 +52,22 @@ impl TemplateList {
     pub(crate) fn set_focus(&mut self, focus: bool) {
         self.has_focus = focus;
     }
+    pub(crate) fn set_reorderable(&mut self, reorderable: bool) -> &mut Self {
+        self.reorderable = reorderable;
+        self
+    }
     pub(crate) fn hit_test(&self, x: u16, y: u16) -> bool {
         self.area.contains(Position::new(x, y))
     }
+    pub(crate) fn set_rule_counts(&mut self, rule_counts: HashMap<String, usize>) -> &mut Self {
+        self.rule_counts = rule_counts;
+        self
+    }
     pub(crate) fn set_templates(&mut self, templates: Vec<String>) -> &mut Self {
         self.templates = templates;
-        self.templates.sort();
+        if !self.reorderable {
+            self.templates.sort();
+        }
         self.list_state.set_size(self.templates.len());
         match self.list_state.selected() {
             Some(selected) => {
@@ -79,9 +98,13 @@ impl TemplateList {
         }
     }
     pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) {
+        let alt = key_event.modifiers == KeyModifiers::ALT;
         match key_event.code {
             KeyCode::Enter => self.send_template_select_event(),
 
+            KeyCode::Up if alt => self.move_selected_by(-1),
+            KeyCode::Down if alt => self.move_selected_by(1),
+
             KeyCode::Home => self.list_state.select_first(),
             KeyCode::PageUp => self.list_state.recede_selected(self.page_size()),
             KeyCode::Up => self.list_state.recede_selected(1),
@@ -101,15 +124,34 @@ impl TemplateList {
     }
     pub(crate) fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
         match mouse_event.kind {
-            MouseEventKind::Up(mouse_button) => {
-                if mouse_button == MouseButton::Left {
-                    if let Some(index) = self.index_from_row(mouse_event.row) {
-                        if self.is_selected(index) {
-                            let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
-                            self.handle_key_event(key_event);
-                        } else {
-                            self.set_selected(index);
-                        }
+            MouseEventKind::Down(MouseButton::Left) if self.reorderable => {
+                self.drag_index = self.index_from_row(mouse_event.row);
+                self.dragged = false;
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let (Some(from), Some(to)) =
+                    (self.drag_index, self.index_from_row(mouse_event.row))
+                {
+                    if from != to {
+                        self.move_selected(from, to);
+                        self.drag_index = Some(to);
+                        self.dragged = true;
+                    }
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.dragged => {
+                // The end of a drag isn't a click
+                self.drag_index = None;
+                self.dragged = false;
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.drag_index = None;
+                if let Some(index) = self.index_from_row(mouse_event.row) {
+                    if self.is_selected(index) {
+                        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+                        self.handle_key_event(key_event);
+                    } else {
+                        self.set_selected(index);
                     }
                 }
             }
@@ -149,6 +191,27 @@ impl TemplateList {
                 .expect("Panic sending template select event");
         }
     }
+    fn move_selected_by(&mut self, distance: isize) {
+        if let Some(selected) = self.list_state.selected() {
+            let index = selected as isize + distance;
+            if self.list_state.is_in_bounds(index) {
+                self.move_selected(selected, index as usize);
+            }
+        }
+    }
+    // Asks for a template to be moved, and moves the highlight along with it.
+    fn move_selected(&mut self, from: usize, to: usize) {
+        if !self.reorderable {
+            return;
+        }
+        let template = self.templates[from].to_string();
+        self.list_state.set_selected(Some(to));
+        self.event_tx
+            .as_ref()
+            .unwrap()
+            .send(Event::TemplateMove(template, to))
+            .expect("Panic sending template move event");
+    }
     fn set_selected(&mut self, index: usize) -> bool {
         if Some(index) == self.list_state.selected() {
             false // Don't bother if the item is already selected
@@ -160,7 +223,11 @@ impl TemplateList {
     fn list_items<'a>(&mut self) -> Vec<ListItem<'a>> {
         self.templates
             .iter()
-            .map(|t| ListItem::new(t.to_string()))
+            .map(|t| match self.rule_counts.get(t) {
+                Some(1) => ListItem::new(format!("{} (1 rule)", t)),
+                Some(count) => ListItem::new(format!("{} ({} rules)", t, count)),
+                None => ListItem::new(t.to_string()),
+            })
             .collect()
     }
     fn page_size(&self) -> usize {
@@ -176,7 +243,7 @@ impl TemplateList {
     }
 }
 impl TemplateList {
-    fn focused_block<'a>() -> Block<'a> {
+    pub(super) fn focused_block<'a>() -> Block<'a> {
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Double)
@@ -184,7 +251,7 @@ impl TemplateList {
             .padding(Padding::horizontal(1))
             .title_style(styles::FOCUSED_TITLE_STYLE)
     }
-    fn default_block<'a>() -> Block<'a> {
+    pub(super) fn default_block<'a>() -> Block<'a> {
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Plain)
@@ -4,6 +4,10 @@
  */
 
 pub(super) mod about;
+pub(super) mod diff;
 pub(super) mod error;
 pub(super) mod help;
+pub(super) mod save_as;
 pub(super) mod save_option;
+pub(super) mod search;
+pub(super) mod suggest;
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-08
+ */
+
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use ratatui::layout::{Margin, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::app::styles;
+use crate::diff::{DiffKind, DiffLine};
+
+/// A scrollable view of the differences between the existing file and the one that would replace it
+#[derive(Default)]
+pub(crate) struct Diff {
+    title: String,
+    lines: Vec<DiffLine>,
+    scroll: usize,
+    area: Rect,
+}
+
+impl Diff {
+    pub(crate) fn set_diff(&mut self, title: &str, lines: Vec<DiffLine>) {
+        self.title = title.to_string();
+        self.lines = lines;
+        self.scroll = 0;
+    }
+    // Returns false when the key closes the popup.
+    pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => return false,
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End => self.scroll = self.max_scroll(),
+            KeyCode::PageUp => self.scroll_by(-(self.page_size() as isize)),
+            KeyCode::PageDown => self.scroll_by(self.page_size() as isize),
+            KeyCode::Up => self.scroll_by(-1),
+            KeyCode::Down => self.scroll_by(1),
+            _ => { /* ignore other keys */ }
+        }
+        true
+    }
+    pub(crate) fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp => self.scroll_by(-1),
+            MouseEventKind::ScrollDown => self.scroll_by(1),
+            _ => { /* ignore other events */ }
+        }
+    }
+    pub(in crate::app) fn render(&mut self, frame: &mut Frame<'_>) {
+        self.area = frame.size().inner(&Margin::new(4, 2));
+        self.scroll = self.scroll.min(self.max_scroll());
+
+        let block = Block::new()
+            .title(format!(
+                "{} (Up/Down/PgUp/PgDn to scroll, Esc to go back)",
+                self.title
+            ))
+            .borders(Borders::ALL)
+            .style(styles::POPUP_BLOCK_STYLE);
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .take(self.page_size())
+            .map(|line| {
+                let style = match line.kind {
+                    DiffKind::Same => styles::POPUP_MESSAGE_STYLE,
+                    DiffKind::Added => styles::DIFF_ADDED_STYLE,
+                    DiffKind::Removed => styles::DIFF_REMOVED_STYLE,
+                };
+                Line::styled(line.to_string(), style)
+            })
+            .collect();
+        let content = Paragraph::new(lines).style(styles::POPUP_MESSAGE_STYLE);
+
+        frame.render_widget(Clear, self.area); // This clears the background underneath the popup
+        frame.render_widget(block, self.area);
+        frame.render_widget(content, self.area.inner(&Margin::new(1, 1)));
+    }
+}
+impl Diff {
+    fn page_size(&self) -> usize {
+        self.area.height.saturating_sub(2) as usize
+    }
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.page_size())
+    }
+    fn scroll_by(&mut self, distance: isize) {
+        self.scroll =
+            (self.scroll as isize + distance).clamp(0, self.max_scroll() as isize) as usize;
+    }
+}
@@ -16,11 +16,29 @@ pub(crate) fn render(frame: &mut Frame) {
         Line::from("* Select the templates to include in the file."),
         Line::from("  - Use the up and down arrows to highlight a template."),
         Line::from("  - Press the space bar to select the highlighted template."),
-        Line::from("  - Type all or part of a template's name to filter the list."),
+        Line::from("  - Type all or part of a template's name to filter the list. Separate names with commas to filter on several at once."),
+        Line::from("  - Left and Right move the cursor in the filter, Ctrl+Left and Ctrl+Right move by word, and Home and End move to the start or end."),
+        Line::from("    With nothing typed in, Home and End go to the first or last template, and Ctrl+Home and Ctrl+End always move the cursor."),
+        Line::from("  - Ctrl+W deletes the word before the cursor, Ctrl+U and Ctrl+K delete to the start or end, and Esc clears the filter."),
+        Line::from("  - Ctrl+Up and Ctrl+Down recall earlier filters."),
+        Line::from("  - Paste a list of template names to select them all at once."),
+        Line::from("  - Press Ctrl+F to search the templates' rules instead of their names, and Enter to show only the templates that match. Esc shows them all again."),
+        Line::default(),
+        Line::from("* Arrange the selected templates in the order they should appear in the file."),
+        Line::from("  - Press Alt+Up or Alt+Down to move the highlighted template, or drag it with the mouse."),
+        Line::default(),
+        Line::from("* Check what the selected templates would ignore in the Files pane, where it's dimmed, and struck through if it isn't ignored now. Press Tab to move to it."),
+        Line::default(),
+        Line::from("* Press Ctrl+T to type a path instead of a filter, and see whether the selected templates would ignore it and which rule decides it. Press Ctrl+T again to go back to the filter."),
         Line::default(),
         Line::from("* Press Ctrl+S to write the .gitignore file to disk."),
-        Line::from("  - The .gitignore file will be written to the current directory."),
-        Line::from("  - If the .gitignore file already exists, you will be given the option of replacing it or appending to it."),
+        Line::from("  - The .gitignore file will be written to the top of the git repository (or the current directory with --here, or outside a repository), unless another file was given with --output."),
+        Line::from("  - If the selected templates conflict, for instance one re-includes something another ignores, the conflicts show on a status line first. Press Ctrl+S again to save anyway."),
+        Line::from("* Press Ctrl+O to choose where to write the file."),
+        Line::from("  - Type a path, pressing Tab to complete it, or use Up and Down to pick the repository root, the repository's exclude file, the global excludes file or a subdirectory."),
+        Line::from("  - If the .gitignore file already exists, you will be given the option of replacing it, appending to it, or merging in only the rules it doesn't already have. You can also look at the differences first."),
+        Line::default(),
+        Line::from("* With gi-gen suggest, pick templates and patterns for the untracked files that nothing ignores yet, and Enter adds them. Press Ctrl+G to pick again."),
         Line::default(),
         Line::from("* Press Ctrl+Q to close the app without writing the .gitignore file."),
     ];
@@ -42,7 +60,7 @@ fn calculate_area(rect: Rect) -> Rect {
     let vertical_layout = Layout::default()
         .constraints([
             Constraint::Fill(0),
-            Constraint::Length(25),
+            Constraint::Length(67),
             Constraint::Fill(0),
         ])
         .split(rect);
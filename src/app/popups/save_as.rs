@@ -0,0 +1,281 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-06
+ */
+
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+use crossterm::event::KeyCode::Char;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Layout, Margin, Position, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, WidgetRef};
+use ratatui::Frame;
+
+use crate::app::styles;
+use crate::app::widgets::text_input::TextInput;
+use crate::{git, util};
+
+#[derive(Default)]
+struct FrameSet {
+    popup: Rect,
+    input: Rect,
+    targets: Rect,
+    hint: Rect,
+}
+
+pub(crate) enum SaveAsAction {
+    Save(PathBuf),
+    Cancel,
+}
+
+// A place people commonly want to put ignore rules
+struct QuickTarget {
+    label: &'static str,
+    path: String,
+}
+
+pub(crate) struct SaveAs {
+    input: TextInput,
+    targets: Vec<QuickTarget>,
+    target_index: Option<usize>,
+    frame_set: FrameSet,
+}
+
+impl Default for SaveAs {
+    fn default() -> Self {
+        Self {
+            input: TextInput::new("Path"),
+            targets: vec![],
+            target_index: None,
+            frame_set: FrameSet::default(),
+        }
+    }
+}
+
+impl SaveAs {
+    const WIDTH: u16 = 72;
+    const LABEL_WIDTH: usize = 20;
+
+    /// Gets the popup ready to show, starting with the current output path.
+    pub(crate) fn open(&mut self, output: &Path) {
+        self.input.set_text(output.to_string_lossy().as_ref());
+        self.target_index = None;
+        self.targets = Self::quick_targets();
+    }
+    pub(crate) fn paste(&mut self, text: &str) {
+        self.input.insert_str(text.trim());
+    }
+    pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<SaveAsAction> {
+        match key_event.code {
+            KeyCode::Esc => return Some(SaveAsAction::Cancel),
+            KeyCode::Enter => return self.save_action(),
+            KeyCode::Tab => {
+                if let Some(completed) = Self::complete(self.input.text()) {
+                    self.input.set_text(&completed);
+                }
+            }
+            KeyCode::Up if key_event.modifiers == KeyModifiers::NONE => self.select_target(-1),
+            KeyCode::Down if key_event.modifiers == KeyModifiers::NONE => self.select_target(1),
+            Char(ch)
+                if key_event.modifiers == KeyModifiers::NONE
+                    || key_event.modifiers == KeyModifiers::SHIFT =>
+            {
+                self.input.insert(ch)
+            }
+            _ => {
+                self.input.handle_key_event(key_event);
+            }
+        }
+        None
+    }
+    pub(crate) fn handle_mouse_event(&mut self, mouse_event: MouseEvent) -> Option<SaveAsAction> {
+        if let MouseEventKind::Up(MouseButton::Left) = mouse_event.kind {
+            let position = Position::new(mouse_event.column, mouse_event.row);
+            if !self.frame_set.popup.contains(position) {
+                return Some(SaveAsAction::Cancel);
+            }
+            if self.frame_set.targets.contains(position) {
+                let index = (mouse_event.row - self.frame_set.targets.y) as usize;
+                if index < self.targets.len() {
+                    if self.target_index == Some(index) {
+                        // A second click on the same target saves there
+                        return self.save_action();
+                    }
+                    self.use_target(index);
+                }
+            }
+        }
+        None
+    }
+    pub(crate) fn cursor_position(&self) -> Position {
+        self.input.cursor_position(self.frame_set.input)
+    }
+    pub(in crate::app) fn render(&mut self, frame: &mut Frame<'_>) {
+        self.frame_set = Self::calculate_frames(frame.size(), self.targets.len());
+
+        let block = Block::new()
+            .title("Save As")
+            .borders(Borders::ALL)
+            .style(styles::POPUP_BLOCK_STYLE);
+        let targets: Vec<Line> = self
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(index, target)| {
+                let line = Line::from(vec![
+                    Span::raw(format!(
+                        "{:width$}",
+                        target.label,
+                        width = Self::LABEL_WIDTH
+                    )),
+                    Span::raw(target.path.to_string()).italic(),
+                ]);
+                if Some(index) == self.target_index {
+                    line.style(styles::LIST_HIGHLIGHT_STYLE)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        let targets = Paragraph::new(targets).style(styles::POPUP_MESSAGE_STYLE);
+        let hint = Paragraph::new(Line::from(vec![
+            Span::styled("Enter", Style::new().reversed()),
+            Span::raw(" Save  "),
+            Span::styled("Tab", Style::new().reversed()),
+            Span::raw(" Complete  "),
+            Span::styled("Up/Down", Style::new().reversed()),
+            Span::raw(" Quick targets  "),
+            Span::styled("Esc", Style::new().reversed()),
+            Span::raw(" Cancel"),
+        ]))
+        .style(styles::POPUP_MESSAGE_STYLE);
+
+        frame.render_widget(Clear, self.frame_set.popup); // This clears the background underneath the popup
+        frame.render_widget(block, self.frame_set.popup);
+        self.input
+            .style(styles::POPUP_MESSAGE_STYLE)
+            .render_ref(self.frame_set.input, frame.buffer_mut());
+        frame.render_widget(targets, self.frame_set.targets);
+        frame.render_widget(hint, self.frame_set.hint);
+    }
+}
+impl SaveAs {
+    fn quick_targets() -> Vec<QuickTarget> {
+        let mut targets = vec![];
+        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let repository = git::Repository::discover(&current_dir);
+        if let Some(repository) = &repository {
+            targets.push(QuickTarget {
+                label: "Repository root",
+                path: repository.gitignore().to_string_lossy().to_string(),
+            });
+            targets.push(QuickTarget {
+                label: "Repository exclude",
+                path: repository.exclude_file().to_string_lossy().to_string(),
+            });
+        }
+        if let Some(excludes_file) = git::global_excludes_file() {
+            targets.push(QuickTarget {
+                label: "Global excludes",
+                path: excludes_file.to_string_lossy().to_string(),
+            });
+        }
+        // A subdirectory is picked with tab completion, starting from the top of the repository
+        let base = repository
+            .as_ref()
+            .map(|repository| repository.work_tree().to_path_buf())
+            .unwrap_or(current_dir);
+        targets.push(QuickTarget {
+            label: "Subdirectory",
+            path: format!("{}{}", base.to_string_lossy(), MAIN_SEPARATOR),
+        });
+        targets
+    }
+    fn select_target(&mut self, distance: isize) {
+        if self.targets.is_empty() {
+            return;
+        }
+        let index = match self.target_index {
+            Some(index) => {
+                (index as isize + distance).clamp(0, self.targets.len() as isize - 1) as usize
+            }
+            None if distance < 0 => self.targets.len() - 1,
+            None => 0,
+        };
+        self.use_target(index);
+    }
+    fn use_target(&mut self, index: usize) {
+        self.target_index = Some(index);
+        let path = self.targets[index].path.to_string();
+        self.input.set_text(&path);
+    }
+    fn save_action(&self) -> Option<SaveAsAction> {
+        let path = self.input.text().trim();
+        if path.is_empty() {
+            None
+        } else {
+            Some(SaveAsAction::Save(util::expand_home(path)))
+        }
+    }
+    // Completes the last component of a path as far as it's the same for every match, and adds a
+    // separator when it completes to a single directory.
+    fn complete(text: &str) -> Option<String> {
+        let split = text.rfind(['/', MAIN_SEPARATOR]).map(|index| index + 1);
+        let (dir_part, prefix) = match split {
+            Some(index) => text.split_at(index),
+            None => ("", text),
+        };
+        let dir = if dir_part.is_empty() {
+            PathBuf::from(".")
+        } else {
+            util::expand_home(dir_part)
+        };
+        let matches: Vec<(String, bool)> = std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let hidden = name.starts_with('.') && !prefix.starts_with('.');
+                if name.starts_with(prefix) && !hidden {
+                    Some((name, entry.path().is_dir()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let (first, _) = matches.first()?;
+        let mut common = first.to_string();
+        for (name, _) in &matches[1..] {
+            while !name.starts_with(common.as_str()) {
+                common.pop();
+            }
+        }
+        if matches.len() == 1 && matches[0].1 {
+            common.push(MAIN_SEPARATOR);
+        }
+        Some(format!("{dir_part}{common}"))
+    }
+    fn calculate_frames(rect: Rect, target_count: usize) -> FrameSet {
+        // Borders, input, blank line, targets, blank line, hint
+        let height = 2 + 1 + 1 + target_count as u16 + 1 + 1;
+        let popup = util::centered_rect(Self::WIDTH, height, rect);
+        let inner = popup.inner(&Margin::new(2, 1));
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(target_count as u16),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+        FrameSet {
+            popup,
+            input: layout[0],
+            targets: layout[2],
+            hint: layout[4],
+        }
+    }
+}
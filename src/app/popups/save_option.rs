@@ -3,6 +3,8 @@
  * Created 2024-04-14
  */
 
+use std::path::Path;
+
 use ratatui::layout::{Constraint, Direction, Layout, Margin, Position, Rect};
 use ratatui::style::{Style, Stylize};
 use ratatui::text::{Line, Span};
@@ -18,32 +20,47 @@ struct FrameSet {
     prompt: Rect,
     replace: Rect,
     append: Rect,
+    merge: Rect,
+    diff: Rect,
     cancel: Rect,
 }
 
+#[derive(Copy, Clone, PartialEq)]
 pub(crate) enum SaveOptions {
     Replace,
     Append,
+    Merge,
+    ShowDiff,
     Cancel,
 }
 
 #[derive(Default)]
 pub(crate) struct SaveOption {
+    path: String,
     frame_set: FrameSet,
 }
 
 impl SaveOption {
     const REPLACE_TEXT: &'static str = "Replace";
     const APPEND_TEXT: &'static str = "Append";
+    const MERGE_TEXT: &'static str = "Merge";
+    const DIFF_TEXT: &'static str = "Show Diff";
     const CANCEL_TEXT: &'static str = "Cancel";
     const REPLACE_CHAR: char = 'r';
     const APPEND_CHAR: char = 'a';
+    const MERGE_CHAR: char = 'm';
+    const DIFF_CHAR: char = 'd';
     const CANCEL_CHAR: char = 'c';
 
+    pub(crate) fn set_path(&mut self, path: &Path) {
+        self.path = path.display().to_string();
+    }
     pub(crate) fn key_test(&self, ch: char) -> Option<SaveOptions> {
         match ch {
             Self::REPLACE_CHAR => Some(SaveOptions::Replace),
             Self::APPEND_CHAR => Some(SaveOptions::Append),
+            Self::MERGE_CHAR => Some(SaveOptions::Merge),
+            Self::DIFF_CHAR => Some(SaveOptions::ShowDiff),
             Self::CANCEL_CHAR => Some(SaveOptions::Cancel),
             _ => None,
         }
@@ -54,6 +71,10 @@ impl SaveOption {
             Some(SaveOptions::Replace)
         } else if self.frame_set.append.contains(position) {
             Some(SaveOptions::Append)
+        } else if self.frame_set.merge.contains(position) {
+            Some(SaveOptions::Merge)
+        } else if self.frame_set.diff.contains(position) {
+            Some(SaveOptions::ShowDiff)
         } else if self.frame_set.cancel.contains(position)
             || !self.frame_set.popup.contains(position)
         {
@@ -66,17 +87,21 @@ impl SaveOption {
         let block = Block::new()
             .borders(Borders::ALL)
             .style(styles::POPUP_BLOCK_STYLE);
-        let prompt = Paragraph::new("A .gitignore file already exists in the current directory.")
+        let prompt = Paragraph::new(format!("{} already exists.", self.path))
             .style(styles::POPUP_MESSAGE_STYLE)
             .wrap(Wrap { trim: true });
-        let replace_prompt = Self::format_prompt(Self::REPLACE_TEXT);
-        let append_prompt = Self::format_prompt(Self::APPEND_TEXT);
-        let cancel_prompt = Self::format_prompt(Self::CANCEL_TEXT);
+        let replace_prompt = Self::format_prompt(Self::REPLACE_TEXT, Self::REPLACE_CHAR);
+        let append_prompt = Self::format_prompt(Self::APPEND_TEXT, Self::APPEND_CHAR);
+        let merge_prompt = Self::format_prompt(Self::MERGE_TEXT, Self::MERGE_CHAR);
+        let diff_prompt = Self::format_prompt(Self::DIFF_TEXT, Self::DIFF_CHAR);
+        let cancel_prompt = Self::format_prompt(Self::CANCEL_TEXT, Self::CANCEL_CHAR);
 
         self.frame_set = Self::calculate_frames(
             frame.size(),
             Self::REPLACE_TEXT.len(),
             Self::APPEND_TEXT.len(),
+            Self::MERGE_TEXT.len(),
+            Self::DIFF_TEXT.len(),
             Self::CANCEL_TEXT.len(),
         );
 
@@ -85,17 +110,23 @@ impl SaveOption {
         frame.render_widget(prompt, self.frame_set.prompt);
         frame.render_widget(replace_prompt, self.frame_set.replace);
         frame.render_widget(append_prompt, self.frame_set.append);
+        frame.render_widget(merge_prompt, self.frame_set.merge);
+        frame.render_widget(diff_prompt, self.frame_set.diff);
         frame.render_widget(cancel_prompt, self.frame_set.cancel);
     }
-    fn format_prompt(prompt: &str) -> Paragraph {
-        let prompt = prompt.as_bytes();
-        let first = std::str::from_utf8(&prompt[0..1]).expect("Bad input");
-        let rest = std::str::from_utf8(&prompt[1..]).expect("Bad input");
+    // Highlights the first occurrence of the option's key in its text.
+    fn format_prompt(prompt: &str, key: char) -> Paragraph<'_> {
+        let index = prompt
+            .find(|ch: char| ch.to_ascii_lowercase() == key)
+            .expect("Bad input");
+        let (before, rest) = prompt.split_at(index);
+        let (key, after) = rest.split_at(1);
 
         Paragraph::new(vec![Line::from(vec![
             Span::raw("["),
-            Span::styled(first, Style::new().reversed()),
-            Span::raw(rest),
+            Span::raw(before),
+            Span::styled(key, Style::new().reversed()),
+            Span::raw(after),
             Span::raw("]"),
         ])])
         .style(styles::POPUP_MESSAGE_STYLE)
@@ -104,12 +135,14 @@ impl SaveOption {
         rect: Rect,
         replace_width: usize,
         append_width: usize,
+        merge_width: usize,
+        diff_width: usize,
         cancel_width: usize,
     ) -> FrameSet {
         let vertical_layout = Layout::default()
             .constraints([
                 Constraint::Fill(1),
-                Constraint::Length(9),
+                Constraint::Length(13),
                 Constraint::Fill(1),
             ])
             .split(rect);
@@ -117,7 +150,7 @@ impl SaveOption {
             .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Fill(0),
-                Constraint::Length(30),
+                Constraint::Length(50),
                 Constraint::Fill(0),
             ])
             .split(vertical_layout[1]);
@@ -132,11 +165,15 @@ impl SaveOption {
                 Constraint::Length(1),
                 Constraint::Length(1),
                 Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
             ])
             .split(inner_layout[1]);
         let replace = Self::calculate_button_rect(button_layout[0], replace_width);
         let append = Self::calculate_button_rect(button_layout[1], append_width);
-        let cancel = Self::calculate_button_rect(button_layout[2], cancel_width);
+        let merge = Self::calculate_button_rect(button_layout[2], merge_width);
+        let diff = Self::calculate_button_rect(button_layout[3], diff_width);
+        let cancel = Self::calculate_button_rect(button_layout[4], cancel_width);
 
         let popup = util::centered_rect(popup.width, popup.height, rect);
         FrameSet {
@@ -144,6 +181,8 @@ impl SaveOption {
             prompt,
             replace,
             append,
+            merge,
+            diff,
             cancel,
         }
     }
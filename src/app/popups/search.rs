@@ -0,0 +1,186 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-10
+ */
+
+use crossterm::event::KeyCode::Char;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Layout, Margin, Position, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, WidgetRef};
+use ratatui::Frame;
+
+use crate::app::list_state_wrapper::ListStateWrapper;
+use crate::app::styles;
+use crate::app::widgets::text_input::{InputResult, TextInput};
+use crate::search::Found;
+
+#[derive(Default)]
+struct FrameSet {
+    popup: Rect,
+    input: Rect,
+    list: Rect,
+    hint: Rect,
+}
+
+pub(crate) enum SearchAction {
+    /// The text to search for changed
+    Search(String),
+    /// Show only these templates, which contain the text
+    Narrow(String, Vec<String>),
+    Close,
+}
+
+/// Searches the templates' rules, showing the templates that have the text with the lines that
+/// have it
+pub(crate) struct Search {
+    input: TextInput,
+    found: Vec<Found>,
+    // A template's name, then each of its lines that has the text
+    rows: Vec<Line<'static>>,
+    list_state: ListStateWrapper,
+    frame_set: FrameSet,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            input: TextInput::new("Search"),
+            found: vec![],
+            rows: vec![],
+            list_state: ListStateWrapper::default(),
+            frame_set: FrameSet::default(),
+        }
+    }
+}
+
+impl Search {
+    pub(crate) fn set_found(&mut self, found: Vec<Found>) {
+        self.rows = found
+            .iter()
+            .flat_map(|template| {
+                let name = Line::from(Span::raw(template.name.clone()).bold());
+                let lines = template
+                    .lines
+                    .iter()
+                    .map(|line| Line::from(format!("  {:>4}: {}", line.number, line.text)));
+                std::iter::once(name).chain(lines)
+            })
+            .collect();
+        self.found = found;
+        self.list_state.set_size(self.rows.len());
+        self.list_state.select_first();
+    }
+    pub(crate) fn paste(&mut self, text: &str) -> SearchAction {
+        let line = text.lines().find(|line| !line.trim().is_empty());
+        self.input.insert_str(line.unwrap_or_default().trim());
+        SearchAction::Search(self.input.text().to_string())
+    }
+    pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<SearchAction> {
+        let plain = key_event.modifiers == KeyModifiers::NONE;
+        match key_event.code {
+            KeyCode::Esc => return Some(SearchAction::Close),
+            KeyCode::Enter => return self.narrow_action(),
+            KeyCode::PageUp if plain => self.list_state.recede_selected(self.page_size()),
+            KeyCode::Up if plain => self.list_state.recede_selected(1),
+            KeyCode::PageDown if plain => self.list_state.advance_selected(self.page_size()),
+            KeyCode::Down if plain => self.list_state.advance_selected(1),
+            Char(ch) if plain || key_event.modifiers == KeyModifiers::SHIFT => {
+                self.input.insert(ch);
+                return Some(SearchAction::Search(self.input.text().to_string()));
+            }
+            _ => {
+                if self.input.handle_key_event(key_event) == InputResult::Changed {
+                    return Some(SearchAction::Search(self.input.text().to_string()));
+                }
+            }
+        }
+        None
+    }
+    pub(crate) fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp => self.list_state.recede_selected(1),
+            MouseEventKind::ScrollDown => self.list_state.advance_selected(1),
+            _ => { /* ignore other events */ }
+        }
+    }
+    pub(crate) fn cursor_position(&self) -> Position {
+        self.input.cursor_position(self.frame_set.input)
+    }
+    pub(in crate::app) fn render(&mut self, frame: &mut Frame<'_>) {
+        self.frame_set = Self::calculate_frames(frame.size());
+
+        let block = Block::new()
+            .title("Search the templates' rules")
+            .borders(Borders::ALL)
+            .style(styles::POPUP_BLOCK_STYLE);
+        let title = if self.input.text().trim().is_empty() {
+            String::new()
+        } else {
+            format!("{} template(s)", self.found.len())
+        };
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| ListItem::new(row.clone()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::new().borders(Borders::TOP).title(title))
+            .style(styles::POPUP_MESSAGE_STYLE)
+            .highlight_style(styles::LIST_HIGHLIGHT_STYLE);
+        let hint = Paragraph::new(Line::from(vec![
+            Span::styled("Enter", Style::new().reversed()),
+            Span::raw(" Show only these templates  "),
+            Span::styled("Up/Down", Style::new().reversed()),
+            Span::raw(" Scroll  "),
+            Span::styled("Esc", Style::new().reversed()),
+            Span::raw(" Cancel"),
+        ]))
+        .style(styles::POPUP_MESSAGE_STYLE);
+
+        frame.render_widget(Clear, self.frame_set.popup); // This clears the background underneath the popup
+        frame.render_widget(block, self.frame_set.popup);
+        self.input
+            .style(styles::POPUP_MESSAGE_STYLE)
+            .render_ref(self.frame_set.input, frame.buffer_mut());
+        frame.render_stateful_widget(list, self.frame_set.list, &mut self.list_state.list_state);
+        frame.render_widget(hint, self.frame_set.hint);
+    }
+}
+impl Search {
+    fn narrow_action(&self) -> Option<SearchAction> {
+        if self.found.is_empty() {
+            return None;
+        }
+        let names = self
+            .found
+            .iter()
+            .map(|template| template.name.clone())
+            .collect();
+        Some(SearchAction::Narrow(
+            self.input.text().trim().to_string(),
+            names,
+        ))
+    }
+    fn page_size(&self) -> usize {
+        self.frame_set.list.height.saturating_sub(1) as usize
+    }
+    fn calculate_frames(rect: Rect) -> FrameSet {
+        let popup = rect.inner(&Margin::new(4, 2));
+        let inner = popup.inner(&Margin::new(1, 1));
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+        FrameSet {
+            popup,
+            input: layout[0],
+            list: layout[1],
+            hint: layout[2],
+        }
+    }
+}
@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-03
+ */
+
+use crossterm::event::KeyCode::Char;
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Margin, Position, Rect};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem};
+use ratatui::Frame;
+
+use crate::app::list_state_wrapper::ListStateWrapper;
+use crate::app::styles;
+use crate::suggest::Suggestions;
+
+pub(crate) enum SuggestAction {
+    /// Use the picked templates and patterns
+    Use(Vec<String>, Vec<String>),
+    Close,
+}
+
+// A template or pattern that would ignore some of the untracked files
+struct Item {
+    template: bool,
+    value: String,
+    label: String,
+    picked: bool,
+}
+
+/// The templates and patterns that would ignore untracked files, to pick from
+#[derive(Default)]
+pub(crate) struct Suggest {
+    items: Vec<Item>,
+    list_state: ListStateWrapper,
+    area: Rect,
+}
+
+impl Suggest {
+    // How many of the paths a suggestion matches to show
+    const EXAMPLES: usize = 3;
+
+    pub(crate) fn set_suggestions(&mut self, suggestions: &Suggestions) {
+        let templates = suggestions.templates.iter().map(|template| {
+            let mut label = format!(
+                "Template {:<20} {}",
+                template.name,
+                Self::describe(&template.paths)
+            );
+            if template.tracked > 0 {
+                label.push_str(&format!(
+                    " (and {} tracked file(s), which git keeps tracking)",
+                    template.tracked
+                ));
+            }
+            Item {
+                template: true,
+                value: template.name.clone(),
+                label,
+                picked: false,
+            }
+        });
+        let patterns = suggestions.patterns.iter().map(|suggestion| Item {
+            template: false,
+            value: suggestion.pattern.clone(),
+            label: format!(
+                "Pattern  {:<20} {}",
+                suggestion.pattern,
+                Self::describe(&suggestion.paths)
+            ),
+            picked: false,
+        });
+        self.items = templates.chain(patterns).collect();
+        self.list_state.set_size(self.items.len());
+        self.list_state.select_first();
+    }
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+    pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<SuggestAction> {
+        match key_event.code {
+            KeyCode::Esc => return Some(SuggestAction::Close),
+            KeyCode::Enter => return Some(self.use_action()),
+            Char(' ') => self.toggle_selected(),
+            KeyCode::Home => self.list_state.select_first(),
+            KeyCode::PageUp => self.list_state.recede_selected(self.page_size()),
+            KeyCode::Up => self.list_state.recede_selected(1),
+            KeyCode::End => self.list_state.select_last(),
+            KeyCode::PageDown => self.list_state.advance_selected(self.page_size()),
+            KeyCode::Down => self.list_state.advance_selected(1),
+            _ => { /* ignore other keys */ }
+        }
+        None
+    }
+    pub(crate) fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        match mouse_event.kind {
+            MouseEventKind::Up(MouseButton::Left) => {
+                let position = Position::new(mouse_event.column, mouse_event.row);
+                let list_area = self.area.inner(&Margin::new(1, 1));
+                if list_area.contains(position) {
+                    let index = (mouse_event.row - list_area.y) as usize + self.list_state.offset();
+                    if index < self.items.len() {
+                        // A click on the highlighted item picks it
+                        if self.list_state.selected() == Some(index) {
+                            self.toggle_selected();
+                        } else {
+                            self.list_state.set_selected(Some(index));
+                        }
+                    }
+                }
+            }
+            MouseEventKind::ScrollUp => self.list_state.recede_selected(1),
+            MouseEventKind::ScrollDown => self.list_state.advance_selected(1),
+            _ => { /* ignore other events */ }
+        }
+    }
+    pub(in crate::app) fn render(&mut self, frame: &mut Frame<'_>) {
+        self.area = frame.size().inner(&Margin::new(4, 2));
+
+        let block = Block::new()
+            .title("Untracked files (Space to pick, Enter to use the picks, Esc to go back)")
+            .borders(Borders::ALL)
+            .style(styles::POPUP_BLOCK_STYLE);
+        let items: Vec<ListItem> = self
+            .items
+            .iter()
+            .map(|item| {
+                let check = if item.picked { "[x]" } else { "[ ]" };
+                ListItem::new(format!("{} {}", check, item.label))
+                    .style(styles::POPUP_MESSAGE_STYLE)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(styles::LIST_HIGHLIGHT_STYLE);
+
+        frame.render_widget(Clear, self.area); // This clears the background underneath the popup
+        frame.render_stateful_widget(list, self.area, &mut self.list_state.list_state);
+    }
+}
+impl Suggest {
+    // Like "3 paths: build/, a.tmp, b.tmp, ..."
+    fn describe(paths: &[String]) -> String {
+        let mut examples = paths
+            .iter()
+            .take(Self::EXAMPLES)
+            .map(|path| path.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ");
+        if paths.len() > Self::EXAMPLES {
+            examples.push_str(", ...");
+        }
+        format!("{} path(s): {}", paths.len(), examples)
+    }
+    fn toggle_selected(&mut self) {
+        if let Some(selected) = self.list_state.selected() {
+            self.items[selected].picked = !self.items[selected].picked;
+        }
+    }
+    fn use_action(&self) -> SuggestAction {
+        let picked = |template: bool| {
+            self.items
+                .iter()
+                .filter(|item| item.picked && item.template == template)
+                .map(|item| item.value.clone())
+                .collect()
+        };
+        SuggestAction::Use(picked(true), picked(false))
+    }
+    fn page_size(&self) -> usize {
+        self.area.height.saturating_sub(2) as usize
+    }
+}
@@ -14,7 +14,11 @@ pub(super) const FOCUSED_TITLE_STYLE: Style = Style::new().fg(Color::Gray).bg(Co
 pub(super) const DEFAULT_BLOCK_STYLE: Style = Style::new().fg(Color::DarkGray).bg(Color::Black);
 pub(super) const DEFAULT_TITLE_STYLE: Style = Style::new().fg(Color::Gray).bg(Color::Black);
 pub(super) const TASK_BAR_STYLE: Style = Style::new().fg(Color::Black).bg(Color::White);
+pub(super) const STATUS_INFO_STYLE: Style = Style::new().fg(Color::Gray).bg(Color::Black);
+pub(super) const STATUS_WARNING_STYLE: Style = Style::new().fg(Color::LightYellow).bg(Color::Black);
 pub(crate) const POPUP_ERROR_MESSAGE_STYLE: Style = Style::new().fg(Color::White).bg(Color::Black);
 pub(crate) const POPUP_ERROR_BLOCK_STYLE: Style = Style::new().fg(Color::LightRed).bg(Color::Black);
 pub(crate) const POPUP_MESSAGE_STYLE: Style = Style::new().fg(Color::White).bg(Color::Black);
 pub(crate) const POPUP_BLOCK_STYLE: Style = Style::new().fg(Color::LightYellow).bg(Color::Black);
+pub(crate) const DIFF_ADDED_STYLE: Style = Style::new().fg(Color::LightGreen).bg(Color::Black);
+pub(crate) const DIFF_REMOVED_STYLE: Style = Style::new().fg(Color::LightRed).bg(Color::Black);
@@ -5,5 +5,7 @@
 
 pub mod filter;
 mod key_button;
+pub mod path_tester;
 pub mod task_action;
 pub mod task_bar;
+pub mod text_input;
@@ -3,44 +3,80 @@
  * Created 2024-04-16
  */
 
+use crossterm::event::KeyCode::Char;
+use crossterm::event::{KeyEvent, KeyModifiers};
 use ratatui::buffer::Buffer;
-use ratatui::layout::Rect;
+use ratatui::layout::{Position, Rect};
 use ratatui::prelude::*;
 use ratatui::widgets::WidgetRef;
 
-#[derive(Default)]
+use crate::app::widgets::text_input::{InputResult, TextInput};
+use crate::util;
+
 pub struct Filter {
-    text: String,
-    style: Style,
+    input: TextInput,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            input: TextInput::new("Filter"),
+        }
+    }
 }
 
 #[allow(dead_code)]
 impl Filter {
     pub fn style(&mut self, style: Style) -> &mut Self {
-        self.style = style;
+        self.input.style(style);
         self
     }
     pub fn text(&self) -> &str {
-        self.text.as_str()
+        self.input.text()
     }
     pub fn set_text(&mut self, text: &str) {
-        self.text = text.to_string();
+        self.input.set_text(text);
     }
     pub fn clear(&mut self) {
-        self.set_text(String::default().as_str());
+        self.input.clear();
     }
-    pub fn push(&mut self, ch: char) -> &str {
-        self.text.push(ch);
-        self.text()
+    // Remembers the current filter in the history, so it can be recalled later.
+    pub fn commit(&mut self) {
+        self.input.commit();
     }
-    pub fn pop(&mut self) -> &str {
-        self.text.pop();
+    // The filter may be a comma-separated list, in which case a template that matches any of the
+    // terms passes.
+    pub fn terms(&self) -> Vec<&str> {
         self.text()
+            .split(',')
+            .map(|term| term.trim())
+            .filter(|term| !term.is_empty())
+            .collect()
+    }
+    // Inserts pasted text at the cursor, turning line breaks and spaces into separators so a
+    // list of templates in just about any layout becomes a comma-separated filter.
+    pub fn paste(&mut self, text: &str) {
+        let terms = util::split_template_list(text);
+        self.input.insert_str(terms.join(",").as_str());
+    }
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> InputResult {
+        match key_event.code {
+            Char(ch)
+                if key_event.modifiers == KeyModifiers::NONE
+                    || key_event.modifiers == KeyModifiers::SHIFT =>
+            {
+                self.input.insert(ch.to_ascii_lowercase());
+                InputResult::Changed
+            }
+            _ => self.input.handle_key_event(key_event),
+        }
+    }
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        self.input.cursor_position(area)
     }
 }
 impl WidgetRef for Filter {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        let text = format!(" Filter: [{}]", self.text);
-        buf.set_string(area.x, area.y, text, self.style);
+        self.input.render_ref(area, buf);
     }
 }
@@ -0,0 +1,127 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-07
+ */
+
+use std::path::Path;
+
+use crossterm::event::KeyCode::Char;
+use crossterm::event::{KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Position, Rect};
+use ratatui::widgets::WidgetRef;
+
+use crate::app::widgets::text_input::{InputResult, TextInput};
+use crate::gitignore::generated::Assembled;
+use crate::gitignore::matcher::{self, Matcher};
+use crate::gitignore::Gitignore;
+use crate::suggest;
+
+/// A path to check against the rules being previewed
+pub struct PathTester {
+    input: TextInput,
+}
+
+impl Default for PathTester {
+    fn default() -> Self {
+        Self {
+            input: TextInput::new("Path"),
+        }
+    }
+}
+
+impl PathTester {
+    pub fn text(&self) -> &str {
+        self.input.text()
+    }
+    pub fn clear(&mut self) {
+        self.input.clear();
+    }
+    pub fn commit(&mut self) {
+        self.input.commit();
+    }
+    // A path is one line, so only the first line of pasted text is used.
+    pub fn paste(&mut self, text: &str) {
+        let line = text.lines().find(|line| !line.trim().is_empty());
+        self.input.insert_str(line.unwrap_or_default().trim());
+    }
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> InputResult {
+        match key_event.code {
+            Char(ch)
+                if key_event.modifiers == KeyModifiers::NONE
+                    || key_event.modifiers == KeyModifiers::SHIFT =>
+            {
+                self.input.insert(ch);
+                InputResult::Changed
+            }
+            _ => self.input.handle_key_event(key_event),
+        }
+    }
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        self.input.cursor_position(area)
+    }
+    /// Says whether the rules ignore the path, and which template's rule decides it, like
+    /// `gi-gen explain`. A path ending in "/", or a directory under `rules_dir`, is checked as a
+    /// directory. Returns `None` if there's no path.
+    pub fn verdict(&self, assembled: &Assembled, rules_dir: &Path) -> Option<String> {
+        let path = self.text().trim();
+        let relative = path.trim_start_matches("./").trim_start_matches('/');
+        if relative.is_empty() {
+            return None;
+        }
+        let is_dir = relative.ends_with('/') || rules_dir.join(relative).is_dir();
+        let relative = relative.trim_end_matches('/');
+        let gitignore = Gitignore::parse(&assembled.content);
+        let Some(found) = Matcher::new(&gitignore).check(relative, is_dir) else {
+            return Some(format!(
+                "{}: not ignored, no selected rule matches it",
+                path
+            ));
+        };
+        let rule = Self::describe_rule(assembled, found.line.number, &found.line.text);
+        if !found.ignored() {
+            return Some(format!("{}: not ignored, re-included by {}", path, rule));
+        }
+        if found.path == relative {
+            return Some(format!("{}: ignored by {}", path, rule));
+        }
+        let mut verdict = format!(
+            "{}: ignored by {}, which matches the directory {}/",
+            path, rule, found.path
+        );
+        // A negation for the path itself is too late once a directory it's in is ignored
+        let last = gitignore
+            .rules()
+            .filter(|(_, rule)| matcher::rule_matches(rule, relative, is_dir))
+            .last();
+        if let Some((line, rule)) = last {
+            if rule.negated {
+                verdict.push_str(&format!(
+                    "; {} can't re-include it from inside an ignored directory",
+                    Self::describe_rule(assembled, line.number, &line.text)
+                ));
+            }
+        }
+        Some(verdict)
+    }
+}
+impl PathTester {
+    // Like `"/target" from rust`
+    fn describe_rule(assembled: &Assembled, number: usize, text: &str) -> String {
+        let template = assembled
+            .templates
+            .iter()
+            .find(|(_, lines)| lines.contains(&(number - 1)))
+            .map(|(name, _)| name.as_str());
+        match template {
+            Some(suggest::SECTION) => format!(r#""{}" from the suggestions"#, text.trim_end()),
+            Some(name) => format!(r#""{}" from {}"#, text.trim_end(), name),
+            None => format!(r#""{}""#, text.trim_end()),
+        }
+    }
+}
+impl WidgetRef for PathTester {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.input.render_ref(area, buf);
+    }
+}
@@ -0,0 +1,288 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-02
+ */
+
+use crossterm::event::KeyCode::Char;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Position, Rect};
+use ratatui::prelude::*;
+use ratatui::widgets::WidgetRef;
+
+/// What a key event did to a text input
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputResult {
+    /// The key isn't one the input handles
+    Ignored,
+    /// The cursor moved, but the text is unchanged
+    Moved,
+    /// The text changed
+    Changed,
+}
+
+/// A single-line text input with a cursor, word-wise editing and history.
+///
+/// The cursor is a character index into the text, not a byte index.
+#[derive(Default)]
+pub struct TextInput {
+    label: String,
+    text: String,
+    cursor: usize,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    draft: String,
+    style: Style,
+}
+
+impl TextInput {
+    const HISTORY_LIMIT: usize = 50;
+
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            ..Self::default()
+        }
+    }
+    pub fn style(&mut self, style: Style) -> &mut Self {
+        self.style = style;
+        self
+    }
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.len();
+    }
+    pub fn clear(&mut self) {
+        self.set_text(String::default().as_str());
+        self.history_index = None;
+    }
+
+    pub fn insert(&mut self, ch: char) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert(index, ch);
+        self.cursor += 1;
+    }
+    pub fn insert_str(&mut self, text: &str) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert_str(index, text);
+        self.cursor += text.chars().count();
+    }
+
+    pub fn move_left(&mut self) -> bool {
+        self.move_to(self.cursor.saturating_sub(1))
+    }
+    pub fn move_right(&mut self) -> bool {
+        self.move_to((self.cursor + 1).min(self.len()))
+    }
+    pub fn move_word_left(&mut self) -> bool {
+        self.move_to(self.prev_word_boundary())
+    }
+    pub fn move_word_right(&mut self) -> bool {
+        self.move_to(self.next_word_boundary())
+    }
+    pub fn move_home(&mut self) -> bool {
+        self.move_to(0)
+    }
+    pub fn move_end(&mut self) -> bool {
+        self.move_to(self.len())
+    }
+
+    pub fn delete_prev(&mut self) -> bool {
+        self.delete_range(self.cursor.saturating_sub(1), self.cursor)
+    }
+    pub fn delete_next(&mut self) -> bool {
+        self.delete_range(self.cursor, (self.cursor + 1).min(self.len()))
+    }
+    pub fn delete_word_prev(&mut self) -> bool {
+        self.delete_range(self.prev_word_boundary(), self.cursor)
+    }
+    pub fn delete_word_next(&mut self) -> bool {
+        self.delete_range(self.cursor, self.next_word_boundary())
+    }
+    pub fn delete_to_start(&mut self) -> bool {
+        self.delete_range(0, self.cursor)
+    }
+    pub fn delete_to_end(&mut self) -> bool {
+        self.delete_range(self.cursor, self.len())
+    }
+
+    // Adds the current text to the history, unless it's empty or the same as the most recent entry.
+    pub fn commit(&mut self) {
+        self.history_index = None;
+        if self.text.is_empty() || self.history.last() == Some(&self.text) {
+            return;
+        }
+        self.history.push(self.text.to_string());
+        if self.history.len() > Self::HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+    }
+    pub fn history_prev(&mut self) -> bool {
+        let index = match self.history_index {
+            Some(0) => return false,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return false,
+            None => {
+                self.draft = self.text.to_string();
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        let text = self.history[index].to_string();
+        self.set_text(&text);
+        true
+    }
+    pub fn history_next(&mut self) -> bool {
+        match self.history_index {
+            None => false,
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                let text = self.history[index + 1].to_string();
+                self.set_text(&text);
+                true
+            }
+            Some(_) => {
+                self.history_index = None;
+                let text = std::mem::take(&mut self.draft);
+                self.set_text(&text);
+                true
+            }
+        }
+    }
+
+    /// Handles the editing keys, leaving anything else to the caller.
+    ///
+    /// Plain characters aren't handled here, because what to do with them depends on the input.
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> InputResult {
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key_event.modifiers.contains(KeyModifiers::ALT);
+        let changed = match key_event.code {
+            KeyCode::Left if ctrl || alt => {
+                self.move_word_left();
+                false
+            }
+            KeyCode::Left => {
+                self.move_left();
+                false
+            }
+            KeyCode::Right if ctrl || alt => {
+                self.move_word_right();
+                false
+            }
+            KeyCode::Right => {
+                self.move_right();
+                false
+            }
+            KeyCode::Home => {
+                self.move_home();
+                false
+            }
+            KeyCode::End => {
+                self.move_end();
+                false
+            }
+            KeyCode::Up if ctrl => self.history_prev(),
+            KeyCode::Down if ctrl => self.history_next(),
+            KeyCode::Backspace if ctrl || alt => self.delete_word_prev(),
+            KeyCode::Backspace => self.delete_prev(),
+            KeyCode::Delete if ctrl => self.delete_word_next(),
+            KeyCode::Delete => self.delete_next(),
+            Char('w') if ctrl => self.delete_word_prev(),
+            Char('d') if alt => self.delete_word_next(),
+            Char('u') if ctrl => self.delete_to_start(),
+            Char('k') if ctrl => self.delete_to_end(),
+            _ => return InputResult::Ignored,
+        };
+        // A key the input handles belongs to it, even if it had nothing to do
+        if changed {
+            InputResult::Changed
+        } else {
+            InputResult::Moved
+        }
+    }
+
+    /// Returns where the terminal cursor belongs when the input is rendered in the given area.
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        let (scroll, _) = self.visible_range(area);
+        let x = area.x + self.prefix().chars().count() as u16 + (self.cursor - scroll) as u16;
+        Position::new(x.min(area.right().saturating_sub(1)), area.y)
+    }
+}
+impl TextInput {
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_index)
+            .map(|(index, _)| index)
+            .unwrap_or(self.text.len())
+    }
+    fn move_to(&mut self, cursor: usize) -> bool {
+        let moved = cursor != self.cursor;
+        self.cursor = cursor;
+        moved
+    }
+    fn delete_range(&mut self, start: usize, end: usize) -> bool {
+        if start >= end {
+            return false;
+        }
+        let (start_byte, end_byte) = (self.byte_index(start), self.byte_index(end));
+        self.text.replace_range(start_byte..end_byte, "");
+        self.cursor = start;
+        true
+    }
+    fn is_word_char(ch: char) -> bool {
+        ch.is_alphanumeric() || ch == '_'
+    }
+    // Skips back over any separators, then over the word before the cursor.
+    fn prev_word_boundary(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut index = self.cursor;
+        while index > 0 && !Self::is_word_char(chars[index - 1]) {
+            index -= 1;
+        }
+        while index > 0 && Self::is_word_char(chars[index - 1]) {
+            index -= 1;
+        }
+        index
+    }
+    // Skips forward over any separators, then over the word after the cursor.
+    fn next_word_boundary(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut index = self.cursor;
+        while index < chars.len() && !Self::is_word_char(chars[index]) {
+            index += 1;
+        }
+        while index < chars.len() && Self::is_word_char(chars[index]) {
+            index += 1;
+        }
+        index
+    }
+    fn prefix(&self) -> String {
+        format!(" {}: [", self.label)
+    }
+    // The first visible character and the number of characters that fit, scrolled so the cursor
+    // is always visible.
+    fn visible_range(&self, area: Rect) -> (usize, usize) {
+        let width = (area.width as usize).saturating_sub(self.prefix().chars().count() + 1);
+        let scroll = if width == 0 {
+            self.cursor
+        } else {
+            (self.cursor + 1).saturating_sub(width)
+        };
+        (scroll, width)
+    }
+}
+impl WidgetRef for TextInput {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let (scroll, width) = self.visible_range(area);
+        let visible: String = self.text.chars().skip(scroll).take(width).collect();
+        let text = format!("{}{}]", self.prefix(), visible);
+        buf.set_stringn(area.x, area.y, text, area.width as usize, self.style);
+    }
+}
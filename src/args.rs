@@ -3,6 +3,13 @@
  * Created 2024-04-10
  */
 
+use std::path::PathBuf;
+
+use crate::gitignore::annotate::Annotate;
+use crate::gitignore::export::Kind;
+use crate::gitignore::generated::Layout;
+use crate::writer::LineEnding;
+
 #[derive(Debug, clap::Args)]
 pub(super) struct FilterArgs {
     /// Filter (regular expression) to apply to list of templates
@@ -12,18 +19,190 @@ pub(super) struct FilterArgs {
 #[derive(Debug, clap::Args)]
 pub(super) struct TemplateArgs {
     /// One or more gitignore templates
-    #[arg(name = "template", required = true)]
+    #[arg(name = "template", required_unless_present = "lock")]
     pub(super) templates: Vec<String>,
 }
 
+#[derive(Debug, Default, clap::Args)]
+pub(super) struct RuleArgs {
+    /// Comment out rules that an earlier template already has
+    #[arg(long)]
+    pub(super) dedupe: bool,
+    /// Drop rules that can't change what's ignored, saying why on stderr
+    #[arg(long)]
+    pub(super) minimize: bool,
+    /// How to put the templates together
+    #[arg(long, value_enum, default_value_t)]
+    pub(super) layout: Layout,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct GenerateArgs {
+    #[command(flatten)]
+    pub(super) template_args: TemplateArgs,
+    #[command(flatten)]
+    pub(super) rule_args: RuleArgs,
+    /// Line endings to use [default: as the API returns them]
+    #[arg(long, value_enum)]
+    pub(super) eol: Option<LineEnding>,
+    /// Save to a file or directory instead of writing to stdout [default: .gitignore at the top of the repository]
+    #[arg(short, long)]
+    pub(super) output: Option<Option<PathBuf>>,
+    #[command(flatten)]
+    pub(super) target_args: TargetArgs,
+    /// Generate from the template content pinned in .gi-gen.lock, pinning any new templates
+    #[arg(long, conflicts_with = "layout")]
+    pub(super) lock: bool,
+    /// Leave out templates that can't be fetched, with a warning, instead of failing
+    #[arg(long, conflicts_with = "lock")]
+    pub(super) allow_missing: bool,
+    /// Record which template each rule came from
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "comments")]
+    pub(super) annotate: Option<Annotate>,
+    /// Write the rules as another tool's ignore file, saving it next to where the .gitignore would go
+    #[arg(long, value_enum)]
+    pub(super) target: Option<Kind>,
+}
+
+#[derive(Debug, Default, clap::Args)]
+pub(super) struct TargetArgs {
+    /// Use the .gitignore in the current directory instead of the one at the top of the repository
+    #[arg(long)]
+    pub(super) here: bool,
+    /// Don't back up an existing file before changing it
+    #[arg(long)]
+    pub(super) no_backup: bool,
+}
+
+#[derive(Debug, Default, clap::Args)]
+pub(super) struct InteractiveArgs {
+    /// File or directory to save the .gitignore to [default: .gitignore at the top of the repository]
+    #[arg(short, long)]
+    pub(super) output: Option<PathBuf>,
+    #[command(flatten)]
+    pub(super) target_args: TargetArgs,
+    #[command(flatten)]
+    pub(super) rule_args: RuleArgs,
+    /// Record which template each rule came from
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "comments")]
+    pub(super) annotate: Option<Annotate>,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct UndoArgs {
+    /// File to restore [default: .gitignore at the top of the repository]
+    pub(super) file: Option<PathBuf>,
+    /// Restore the .gitignore in the current directory instead of the one at the top of the repository
+    #[arg(long)]
+    pub(super) here: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct RulesSourceArgs {
+    /// Check against the rules these templates would generate instead of an existing file
+    #[arg(short, long = "template", value_delimiter = ',')]
+    pub(super) templates: Vec<String>,
+    /// File of ignore rules to check against [default: .gitignore at the top of the repository]
+    #[arg(short, long, conflicts_with = "templates")]
+    pub(super) file: Option<PathBuf>,
+    /// Use the .gitignore in the current directory instead of the one at the top of the repository
+    #[arg(long)]
+    pub(super) here: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct ExplainArgs {
+    /// One or more paths to check
+    #[arg(name = "path", required = true)]
+    pub(super) paths: Vec<PathBuf>,
+    #[command(flatten)]
+    pub(super) source_args: RulesSourceArgs,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct AuditArgs {
+    #[command(flatten)]
+    pub(super) source_args: RulesSourceArgs,
+    /// Print the `git rm --cached` commands that stop tracking the files
+    #[arg(long)]
+    pub(super) fix: bool,
+    /// Run the commands instead of printing them
+    #[arg(long, requires = "fix")]
+    pub(super) run: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub(super) enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct LintArgs {
+    /// File to check [default: .gitignore at the top of the repository]
+    pub(super) file: Option<PathBuf>,
+    /// Check the .gitignore in the current directory instead of the one at the top of the repository
+    #[arg(long)]
+    pub(super) here: bool,
+    /// How to write the problems found
+    #[arg(long, value_enum, default_value_t)]
+    pub(super) format: Format,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct CheckArgs {
+    /// File to check [default: .gitignore at the top of the repository]
+    pub(super) file: Option<PathBuf>,
+    #[command(flatten)]
+    pub(super) target_args: TargetArgs,
+    #[command(flatten)]
+    pub(super) rule_args: RuleArgs,
+    /// Replace the generated rules with the current templates
+    #[arg(long)]
+    pub(super) update: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct SuggestArgs {
+    /// Print the suggestions as JSON instead of picking from them interactively
+    #[arg(long)]
+    pub(super) json: bool,
+    #[command(flatten)]
+    pub(super) interactive_args: InteractiveArgs,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct SearchArgs {
+    /// Text to look for in the templates' rules, such as "*.pyc" or ".terraform/"
+    pub(super) pattern: String,
+    /// Print the matching templates and lines as JSON
+    #[arg(long)]
+    pub(super) json: bool,
+}
+
 #[derive(Debug, clap::Subcommand)]
 pub(super) enum Commands {
     /// List available templates with optional filter applied
     List(FilterArgs),
     /// Generate .gitignore using specified template(s)
-    Generate(TemplateArgs),
+    Generate(GenerateArgs),
     /// Pick templates interactively and generate .gitignore (default)
-    Interactive,
+    Interactive(InteractiveArgs),
+    /// Restore a file from the backup taken before gi-gen last changed it
+    Undo(UndoArgs),
+    /// Show which rule, if any, ignores each path
+    Explain(ExplainArgs),
+    /// List tracked files that ignore rules match
+    Audit(AuditArgs),
+    /// Check a .gitignore for rules that don't do what they seem to
+    Lint(LintArgs),
+    /// Check whether the generated rules in a .gitignore are out of date
+    Check(CheckArgs),
+    /// Suggest patterns and templates for untracked files that nothing ignores yet
+    Suggest(SuggestArgs),
+    /// Find the templates whose rules contain some text
+    Search(SearchArgs),
 }
 
 #[derive(Debug, clap::Parser)]
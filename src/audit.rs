@@ -0,0 +1,67 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-22
+ */
+
+use std::path::Path;
+
+use crate::git::Repository;
+use crate::gitignore::matcher::Matcher;
+use crate::gitignore::Gitignore;
+use crate::{git, util};
+
+/// A file git tracks that ignore rules match. Git keeps tracking it regardless, so the rules
+/// don't do what they seem to until it's removed from the index.
+pub(crate) struct Finding {
+    /// Relative to the top of the working tree
+    pub(crate) path: String,
+    /// The line number of the rule that matches it
+    pub(crate) line: usize,
+}
+
+/// Finds the tracked files that the rules in `content` would ignore, if they were in a file of
+/// ignore rules that applies to `rules_dir`.
+pub(crate) fn audit(
+    repository: &Repository,
+    rules_dir: &Path,
+    content: &str,
+) -> std::io::Result<Vec<Finding>> {
+    let Some(prefix) = util::relative_path(repository.work_tree(), rules_dir) else {
+        return Ok(vec![]);
+    };
+    let gitignore = Gitignore::parse(content);
+    let matcher = Matcher::new(&gitignore);
+    let findings = repository
+        .tracked_files()?
+        .into_iter()
+        .filter_map(|path| {
+            let relative = if prefix.is_empty() {
+                path.as_str()
+            } else {
+                path.strip_prefix(prefix.as_str())?.strip_prefix('/')?
+            };
+            let found = matcher.check(relative, false)?;
+            let line = found.line.number;
+            found.ignored().then_some(Finding { path, line })
+        })
+        .collect();
+    Ok(findings)
+}
+
+/// A warning for when the rules just saved to `file` match tracked files
+pub(crate) fn warning(file: &Path, content: &str) -> Option<String> {
+    let rules_dir = git::rules_dir(file);
+    let repository = Repository::discover(&rules_dir)?;
+    let count = audit(&repository, &rules_dir, content).ok()?.len();
+    match count {
+        0 => None,
+        1 => Some(
+            "1 tracked file matches these rules, but git keeps tracking it until it's removed from the index. Run `gi-gen audit` to see it."
+                .to_string(),
+        ),
+        count => Some(format!(
+            "{} tracked files match these rules, but git keeps tracking them until they're removed from the index. Run `gi-gen audit` to see them.",
+            count
+        )),
+    }
+}
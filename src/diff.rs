@@ -0,0 +1,106 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-08
+ */
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum DiffKind {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DiffLine {
+    pub(crate) kind: DiffKind,
+    pub(crate) text: String,
+}
+
+impl DiffLine {
+    fn new(kind: DiffKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let marker = match self.kind {
+            DiffKind::Same => ' ',
+            DiffKind::Added => '+',
+            DiffKind::Removed => '-',
+        };
+        write!(f, "{}{}", marker, self.text)
+    }
+}
+
+/// Compares two texts line by line, using the longest common subsequence of their lines.
+pub(crate) fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Lines at the start and end that haven't changed don't need the expensive part
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    // lengths[i][j] is the length of the longest common subsequence of old_middle[i..] and
+    // new_middle[j..]
+    let mut lengths = vec![vec![0u32; new_middle.len() + 1]; old_middle.len() + 1];
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            lengths[i][j] = if old_middle[i] == new_middle[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut result: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::new(DiffKind::Same, line))
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() && j < new_middle.len() {
+        if old_middle[i] == new_middle[j] {
+            result.push(DiffLine::new(DiffKind::Same, old_middle[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            result.push(DiffLine::new(DiffKind::Removed, old_middle[i]));
+            i += 1;
+        } else {
+            result.push(DiffLine::new(DiffKind::Added, new_middle[j]));
+            j += 1;
+        }
+    }
+    result.extend(
+        old_middle[i..]
+            .iter()
+            .map(|line| DiffLine::new(DiffKind::Removed, line)),
+    );
+    result.extend(
+        new_middle[j..]
+            .iter()
+            .map(|line| DiffLine::new(DiffKind::Added, line)),
+    );
+    result.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::new(DiffKind::Same, line)),
+    );
+    result
+}
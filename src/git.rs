@@ -0,0 +1,331 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-06
+ */
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::util;
+
+/// The parts of a git repository that gi-gen writes ignore rules to
+#[derive(Clone, Debug)]
+pub(crate) struct Repository {
+    work_tree: PathBuf,
+    // Linked worktrees share some things, like info/exclude, with the main repository
+    common_dir: PathBuf,
+}
+
+impl Repository {
+    /// Finds the repository that contains `start` the way git does: `GIT_DIR` (and
+    /// `GIT_WORK_TREE`) if they're set, otherwise the nearest parent directory with a `.git`
+    /// directory, or a `.git` file pointing to the repository of a worktree or submodule, without
+    /// going up into a directory in `GIT_CEILING_DIRECTORIES`.
+    pub(crate) fn discover(start: &Path) -> Option<Self> {
+        let current_dir = std::env::current_dir().ok()?;
+        Self::discover_in(start, &current_dir, |name| std::env::var_os(name))
+    }
+    /// Like `discover`, with the current directory and environment variables given instead of
+    /// taken from the process
+    pub(crate) fn discover_in(
+        start: &Path,
+        current_dir: &Path,
+        var: impl Fn(&str) -> Option<OsString>,
+    ) -> Option<Self> {
+        let start = start.canonicalize().ok()?;
+        let var = |name| var(name).filter(|value| !value.is_empty());
+        if let Some(git_dir) = var("GIT_DIR") {
+            // Like git, these are relative to the current directory, not to `start`
+            let git_dir = current_dir.join(git_dir).canonicalize().ok()?;
+            let work_tree = match var("GIT_WORK_TREE") {
+                Some(work_tree) => current_dir.join(work_tree).canonicalize().ok()?,
+                // Without GIT_WORK_TREE, git takes the current directory as the top of the tree
+                None => current_dir.canonicalize().ok()?,
+            };
+            return Some(Self::new(work_tree, git_dir));
+        }
+        let ceilings = Self::ceiling_dirs(var("GIT_CEILING_DIRECTORIES"));
+        start
+            .ancestors()
+            .take_while(|dir| *dir == start || !ceilings.iter().any(|ceiling| ceiling == dir))
+            .find_map(|dir| {
+                let dot_git = dir.join(".git");
+                if dot_git.is_dir() {
+                    Some(Self::new(dir.to_path_buf(), dot_git))
+                } else if dot_git.is_file() {
+                    let git_dir = Self::read_git_file(&dot_git)?;
+                    Some(Self::new(dir.to_path_buf(), git_dir))
+                } else {
+                    None
+                }
+            })
+    }
+    pub(crate) fn work_tree(&self) -> &Path {
+        &self.work_tree
+    }
+    /// The .gitignore file at the root of the working tree
+    pub(crate) fn gitignore(&self) -> PathBuf {
+        self.work_tree.join(".gitignore")
+    }
+    /// The repository's private exclude file, which isn't committed
+    pub(crate) fn exclude_file(&self) -> PathBuf {
+        self.common_dir.join("info").join("exclude")
+    }
+    /// The files in the index, relative to the top of the working tree with "/" between
+    /// components
+    pub(crate) fn tracked_files(&self) -> std::io::Result<Vec<String>> {
+        self.ls_files(&[])
+    }
+    /// The files that are neither in the index nor ignored, relative to the top of the working
+    /// tree like the tracked ones. A directory with nothing in the index is listed once, ending
+    /// in "/", instead of everything in it.
+    pub(crate) fn untracked_files(&self) -> std::io::Result<Vec<String>> {
+        self.ls_files(&[
+            "--others",
+            "--exclude-standard",
+            "--directory",
+            "--no-empty-directory",
+        ])
+    }
+    /// Stops tracking files without deleting them, as `git rm --cached` does
+    pub(crate) fn untrack(&self, paths: &[String]) -> std::io::Result<()> {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&self.work_tree)
+            .args(["rm", "--cached", "--quiet", "--"])
+            .args(paths)
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(std::io::Error::other(format!("git rm --cached {}", status)))
+        }
+    }
+}
+impl Repository {
+    fn ls_files(&self, args: &[&str]) -> std::io::Result<Vec<String>> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.work_tree)
+            .args(["ls-files", "-z"])
+            .args(args)
+            .output()?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| String::from_utf8_lossy(path).to_string())
+            .collect())
+    }
+    // The directories in GIT_CEILING_DIRECTORIES. Git leaves out any that aren't absolute.
+    fn ceiling_dirs(dirs: Option<OsString>) -> Vec<PathBuf> {
+        dirs.map(|dirs| {
+            std::env::split_paths(&dirs)
+                .filter(|dir| dir.is_absolute())
+                .map(|dir| dir.canonicalize().unwrap_or(dir))
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+    fn new(work_tree: PathBuf, git_dir: PathBuf) -> Self {
+        // A worktree's git directory names the main repository's in its "commondir" file
+        let common_dir = std::fs::read_to_string(git_dir.join("commondir"))
+            .ok()
+            .and_then(|common_dir| git_dir.join(common_dir.trim()).canonicalize().ok())
+            .unwrap_or(git_dir);
+        Self {
+            work_tree,
+            common_dir,
+        }
+    }
+    // Worktrees and submodules have a .git file with a line like "gitdir: ../.git/modules/lib"
+    // instead of a .git directory.
+    fn read_git_file(path: &Path) -> Option<PathBuf> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let git_dir = content
+            .lines()
+            .find_map(|line| line.strip_prefix("gitdir:"))?
+            .trim();
+        path.parent()?.join(git_dir).canonicalize().ok()
+    }
+}
+
+/// Returns the global excludes file: `core.excludesFile` if it's set, or git's default location.
+pub(crate) fn global_excludes_file() -> Option<PathBuf> {
+    let configured = Command::new("git")
+        .args(["config", "--global", "--get", "core.excludesFile"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|path| !path.is_empty());
+    match configured {
+        Some(path) => Some(util::expand_home(&path)),
+        None => match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            Some(config_dir) => Some(PathBuf::from(config_dir).join("git").join("ignore")),
+            None => util::home_dir().map(|home| home.join(".config").join("git").join("ignore")),
+        },
+    }
+}
+
+/// The directory that the paths in a file of ignore rules are relative to: the top of the working
+/// tree for info/exclude and the global excludes file, otherwise the directory the file is in.
+pub(crate) fn rules_dir(file: &Path) -> PathBuf {
+    let file = util::absolute_path(file);
+    let dir = file
+        .parent()
+        .map(|parent| parent.canonicalize().unwrap_or(parent.to_path_buf()))
+        .unwrap_or_default();
+    let repository = match Repository::discover(&dir) {
+        Some(repository) => repository,
+        None => return dir,
+    };
+    let same_file =
+        |other: PathBuf| other.canonicalize().ok() == file.canonicalize().ok() && file.exists();
+    if same_file(repository.exclude_file()) || global_excludes_file().is_some_and(same_file) {
+        repository.work_tree
+    } else {
+        dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+
+    use super::Repository;
+    use crate::testing::TempDir;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| OsString::from(value))
+        }
+    }
+
+    fn mkdir(dir: &TempDir, relative: &str) -> PathBuf {
+        let path = dir.path().join(relative);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn discover(start: &Path, current_dir: &Path, vars: &[(&str, &str)]) -> Option<Repository> {
+        Repository::discover_in(start, current_dir, env(vars))
+    }
+
+    #[test]
+    fn finds_the_nearest_git_directory() {
+        let dir = TempDir::new();
+        let work_tree = dir.path().join("repo");
+        mkdir(&dir, "repo/.git");
+        let start = mkdir(&dir, "repo/src/lib");
+        let repository = discover(&start, dir.path(), &[]).unwrap();
+        assert_eq!(repository.work_tree(), work_tree);
+        assert_eq!(repository.gitignore(), work_tree.join(".gitignore"));
+        assert_eq!(
+            repository.exclude_file(),
+            work_tree.join(".git").join("info").join("exclude")
+        );
+    }
+
+    #[test]
+    fn follows_the_git_file_of_a_worktree() {
+        let dir = TempDir::new();
+        let git_dir = mkdir(&dir, "main/.git/worktrees/feature");
+        std::fs::write(git_dir.join("commondir"), "../..\n").unwrap();
+        dir.write("feature/.git", "gitdir: ../main/.git/worktrees/feature\n");
+        let start = mkdir(&dir, "feature/src");
+        let repository = discover(&start, dir.path(), &[]).unwrap();
+        assert_eq!(repository.work_tree(), dir.path().join("feature"));
+        // A worktree shares the main repository's exclude file
+        assert_eq!(
+            repository.exclude_file(),
+            dir.path().join("main/.git/info/exclude")
+        );
+    }
+
+    #[test]
+    fn follows_the_git_file_of_a_submodule() {
+        let dir = TempDir::new();
+        mkdir(&dir, "super/.git/modules/lib");
+        dir.write("super/lib/.git", "gitdir: ../.git/modules/lib\n");
+        let start = dir.path().join("super/lib");
+        let repository = discover(&start, dir.path(), &[]).unwrap();
+        assert_eq!(repository.work_tree(), start);
+        assert_eq!(
+            repository.exclude_file(),
+            dir.path().join("super/.git/modules/lib/info/exclude")
+        );
+    }
+
+    #[test]
+    fn uses_git_dir_and_git_work_tree() {
+        let dir = TempDir::new();
+        mkdir(&dir, "store.git");
+        mkdir(&dir, "repo/.git");
+        let current_dir = mkdir(&dir, "work/tree");
+        let start = mkdir(&dir, "repo/src");
+        let exclude_file = dir.path().join("store.git/info/exclude");
+
+        // They're relative to the current directory, and `start` doesn't matter
+        let vars = [("GIT_DIR", "../../store.git"), ("GIT_WORK_TREE", "..")];
+        let repository = discover(&start, &current_dir, &vars).unwrap();
+        assert_eq!(repository.work_tree(), dir.path().join("work"));
+        assert_eq!(repository.exclude_file(), exclude_file);
+
+        // Without GIT_WORK_TREE, the current directory is the top of the tree
+        let vars = [("GIT_DIR", "../../store.git")];
+        let repository = discover(&start, &current_dir, &vars).unwrap();
+        assert_eq!(repository.work_tree(), current_dir);
+        assert_eq!(repository.exclude_file(), exclude_file);
+
+        // An empty GIT_DIR is ignored
+        let vars = [("GIT_DIR", ""), ("GIT_WORK_TREE", "..")];
+        let repository = discover(&start, &current_dir, &vars).unwrap();
+        assert_eq!(repository.work_tree(), dir.path().join("repo"));
+
+        let vars = [("GIT_DIR", "missing.git")];
+        assert!(discover(&start, &current_dir, &vars).is_none());
+    }
+
+    #[test]
+    fn stops_at_ceiling_directories() {
+        let dir = TempDir::new();
+        mkdir(&dir, "repo/.git");
+        let start = mkdir(&dir, "repo/src/lib");
+        let repo = dir.path().join("repo").display().to_string();
+        let src = dir.path().join("repo/src").display().to_string();
+        let find = |ceilings: &str| {
+            discover(&start, dir.path(), &[("GIT_CEILING_DIRECTORIES", ceilings)])
+                .map(|repository| repository.work_tree().to_path_buf())
+        };
+
+        assert_eq!(find(&src), None);
+        assert_eq!(find(&repo), None);
+        // Relative directories are left out
+        assert_eq!(find("repo/src"), Some(dir.path().join("repo")));
+        let ceilings = std::env::join_paths(["repo", "/nowhere"]).unwrap();
+        assert_eq!(
+            find(ceilings.to_str().unwrap()),
+            Some(dir.path().join("repo"))
+        );
+        let ceilings = std::env::join_paths([&repo, "/nowhere"]).unwrap();
+        assert_eq!(find(ceilings.to_str().unwrap()), None);
+
+        // The directory it starts in is looked at even when it's a ceiling
+        let repository = discover(
+            &dir.path().join("repo"),
+            dir.path(),
+            &[("GIT_CEILING_DIRECTORIES", &repo)],
+        );
+        assert!(repository.is_some());
+    }
+}
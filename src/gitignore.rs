@@ -0,0 +1,20 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-08
+ */
+
+pub(crate) use parser::{Gitignore, LineKind, Section};
+pub(crate) use rule::Rule;
+
+pub(crate) mod annotate;
+pub(crate) mod conflicts;
+pub(crate) mod dedupe;
+pub(crate) mod export;
+pub(crate) mod generated;
+pub(crate) mod lint;
+pub(crate) mod matcher;
+pub(crate) mod merge;
+pub(crate) mod minimize;
+mod parser;
+mod rule;
+mod wildmatch;
@@ -0,0 +1,135 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-28
+ */
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::gitignore::Gitignore;
+use crate::gitignore_api;
+
+/// How to record which template each rule came from
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub(crate) enum Annotate {
+    /// A comment after each template's rules
+    Comments,
+    /// A JSON file next to the saved .gitignore mapping each rule to its templates
+    Json,
+}
+
+#[derive(serde::Serialize)]
+struct Sidecar<'a> {
+    templates: Vec<SidecarTemplate>,
+    /// Each rule, and the templates it's in
+    rules: BTreeMap<&'a str, Vec<&'a str>>,
+}
+
+#[derive(serde::Serialize)]
+struct SidecarTemplate {
+    name: String,
+    source: String,
+}
+
+/// Adds a comment after each template's rules saying where they came from. Git doesn't allow a
+/// comment on the same line as a rule, so this is as close as it gets.
+pub(crate) fn with_comments(content: &str, templates: &[(String, Range<usize>)]) -> String {
+    let gitignore = Gitignore::parse(content);
+    let mut annotated = String::new();
+    for (index, line) in gitignore.lines().iter().enumerate() {
+        annotated.push_str(&line.text);
+        annotated.push_str(line.ending);
+        let ends_template = templates
+            .iter()
+            .find(|(_, range)| !range.is_empty() && range.end == index + 1);
+        if let Some((name, _)) = ends_template {
+            if line.ending.is_empty() {
+                annotated.push('\n');
+            }
+            annotated.push_str(&format!(
+                "# (from the {} template: {}){}",
+                name,
+                template_url(name),
+                line.ending
+            ));
+        }
+    }
+    annotated
+}
+
+/// Whether a line is one of the comments `with_comments` adds
+pub(crate) fn is_comment(text: &str) -> bool {
+    text.trim_end()
+        .strip_prefix("# (from the ")
+        .is_some_and(|rest| rest.contains(" template: ") && rest.ends_with(')'))
+}
+
+/// A JSON map from each rule to the templates it came from. It's keyed by the rule rather than
+/// by line number, so it still holds after the rules are appended or merged into a file.
+pub(crate) fn sidecar(content: &str, templates: &[(String, Range<usize>)]) -> String {
+    let gitignore = Gitignore::parse(content);
+    let lines = gitignore.lines();
+    let mut rules: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (name, range) in templates {
+        for line in lines.get(range.clone()).unwrap_or_default() {
+            if line.rule().is_some() {
+                let names = rules.entry(line.text.trim()).or_default();
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+    let sidecar = Sidecar {
+        templates: templates
+            .iter()
+            .map(|(name, _)| SidecarTemplate {
+                name: name.clone(),
+                source: template_url(name),
+            })
+            .collect(),
+        rules,
+    };
+    format!(
+        "{}\n",
+        serde_json::to_string_pretty(&sidecar).unwrap_or_default()
+    )
+}
+
+/// Where the JSON map goes for rules saved to `file`
+pub(crate) fn sidecar_path(file: &Path) -> PathBuf {
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(".gitignore".to_string());
+    file.with_file_name(format!("{}.gi-gen.json", name))
+}
+
+fn template_url(name: &str) -> String {
+    gitignore_api::template_url(&[name.to_string()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_comment, with_comments};
+
+    #[test]
+    fn comments_after_each_template() {
+        let content = "### Rust ###\n/target/\n\n### Node ###\nnode_modules/";
+        let templates = [("rust".to_string(), 0..2), ("node".to_string(), 3..5)];
+        let annotated = with_comments(content, &templates);
+        let lines: Vec<&str> = annotated.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[2].starts_with("# (from the rust template: http"));
+        assert!(lines[6].starts_with("# (from the node template: http"));
+        // Without a line ending to copy, the last comment doesn't get one either
+        assert!(annotated.ends_with(')'));
+        let comments: Vec<usize> = (0..lines.len())
+            .filter(|index| is_comment(lines[*index]))
+            .collect();
+        assert_eq!(comments, [2, 6]);
+        assert!(!is_comment("# (from memory)"));
+        assert!(!is_comment("### Rust ###"));
+    }
+}
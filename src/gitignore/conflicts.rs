@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-29
+ */
+
+use std::ops::Range;
+
+use crate::gitignore::lint::lint;
+use crate::gitignore::Gitignore;
+
+/// A rule from one template that another template's rule undoes
+#[derive(Debug)]
+pub(crate) struct Conflict {
+    pub(crate) message: String,
+}
+
+/// Finds rules that contradict a rule from another template: a rule that a later one undoes, or
+/// a negation that can't re-include anything because another template ignores the directory
+/// it's in. `templates` gives the lines each template's rules are on, by index.
+pub(crate) fn conflicts(content: &str, templates: &[(String, Range<usize>)]) -> Vec<Conflict> {
+    let gitignore = Gitignore::parse(content);
+    let lines = gitignore.lines();
+    let template_of = |number: usize| {
+        templates
+            .iter()
+            .find(|(_, range)| range.contains(&(number - 1)))
+            .map(|(name, _)| name.as_str())
+    };
+    lint(content)
+        .into_iter()
+        .filter(|diagnostic| matches!(diagnostic.code, "overridden" | "ineffective-negation"))
+        .filter_map(|diagnostic| {
+            let other = diagnostic.other?;
+            let (template, other_template) = (template_of(diagnostic.line)?, template_of(other)?);
+            if template == other_template {
+                return None;
+            }
+            let text = lines[diagnostic.line - 1].text.trim_end();
+            let other_text = lines[other - 1].text.trim_end();
+            let message = if diagnostic.code == "ineffective-negation" {
+                format!(
+                    r#""{}" ({}) can't re-include anything, because "{}" ({}) ignores a directory it's in"#,
+                    text, template, other_text, other_template
+                )
+            } else if text.starts_with('!') {
+                format!(
+                    r#""{}" ({}) is undone by "{}" ({}), which ignores everything it re-includes"#,
+                    text, template, other_text, other_template
+                )
+            } else {
+                format!(
+                    r#""{}" ({}) is undone by "{}" ({}), which re-includes everything it ignores"#,
+                    text, template, other_text, other_template
+                )
+            };
+            Some(Conflict { message })
+        })
+        .collect()
+}
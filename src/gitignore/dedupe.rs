@@ -0,0 +1,107 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-17
+ */
+
+use std::collections::HashMap;
+
+use crate::gitignore::{Gitignore, LineKind, Rule};
+
+/// Replaces rules that an earlier template already has with a comment saying which one, leaving
+/// everything else as it was.
+///
+/// A rule is only dropped if that can't change the outcome: if an opposing rule (a negation, for
+/// an ordinary rule) comes between the two copies, the later copy is what decides the paths they
+/// both match, so it stays.
+pub(crate) fn dedupe(content: &str) -> String {
+    let gitignore = Gitignore::parse(content);
+    let lines = gitignore.lines();
+    // Where the nearest copy of each rule that's still in the file is: its line index and section
+    let mut seen: HashMap<Rule, (usize, usize)> = HashMap::new();
+    let mut section = 0;
+    let mut section_names: Vec<Option<&str>> = vec![None];
+    let mut deduped = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        if let LineKind::SectionHeader(name) = &line.kind {
+            section += 1;
+            section_names.push(Some(name.as_str()));
+        }
+        if let Some(rule) = line.rule() {
+            let key = rule.normalized();
+            if let Some(&(earlier, earlier_section)) = seen.get(&key) {
+                let opposed = lines[earlier + 1..index]
+                    .iter()
+                    .filter_map(|line| line.rule())
+                    .any(|other| other.negated != rule.negated);
+                if earlier_section != section && !opposed {
+                    let covered_by = match section_names[earlier_section] {
+                        Some(name) => name.to_string(),
+                        None => format!("line {}", lines[earlier].number),
+                    };
+                    deduped.push_str(
+                        format!(
+                            "# {} (already covered by {}){}",
+                            line.text.trim_end(),
+                            covered_by,
+                            line.ending
+                        )
+                        .as_str(),
+                    );
+                    continue;
+                }
+            }
+            seen.insert(key, (index, section));
+        }
+        deduped.push_str(line.text.as_str());
+        deduped.push_str(line.ending);
+    }
+    deduped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dedupe;
+
+    #[test]
+    fn comments_out_a_rule_an_earlier_template_has() {
+        assert_eq!(
+            dedupe("### Node ###\n*.log\n\n### Python ###\n*.log\n.env\n"),
+            "### Node ###\n*.log\n\n### Python ###\n# *.log (already covered by Node)\n.env\n"
+        );
+        // Rules that are written differently but match the same paths are the same
+        assert_eq!(
+            dedupe("### Node ###\r\n*.log\r\n### Python ###\r\n**/*.log  \r\n"),
+            "### Node ###\r\n*.log\r\n### Python ###\r\n# **/*.log (already covered by Node)\r\n"
+        );
+        // A rule before the first section is named by its line
+        assert_eq!(
+            dedupe(".env\n### Python ###\n.env\n"),
+            ".env\n### Python ###\n# .env (already covered by line 1)\n"
+        );
+    }
+
+    #[test]
+    fn keeps_a_rule_an_opposing_rule_comes_before() {
+        // Dropping the second "*.log" would leave debug.log unignored
+        let content = "### Node ###\n*.log\n!debug.log\n### Python ###\n*.log\n";
+        assert_eq!(dedupe(content), content);
+        let content = "### Node ###\n!keep.log\n### Python ###\nkeep.log\n### Go ###\n!keep.log\n";
+        assert_eq!(dedupe(content), content);
+        // A rule of the same polarity in between doesn't matter
+        assert_eq!(
+            dedupe("### Node ###\n*.log\n*.tmp\n### Python ###\n*.log\n"),
+            "### Node ###\n*.log\n*.tmp\n### Python ###\n# *.log (already covered by Node)\n"
+        );
+    }
+
+    #[test]
+    fn leaves_a_repeat_within_a_section_alone() {
+        for content in [
+            "### Node ###\n*.log\n*.log\n",
+            "*.log\n# again\n*.log\n",
+            "### Node ###\n*.log\n### Python ###\n.env\n.env\n",
+        ] {
+            assert_eq!(dedupe(content), content);
+        }
+    }
+}
@@ -0,0 +1,573 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-31
+ */
+
+use crate::gitignore::lint::{lint, may_overlap};
+use crate::gitignore::matcher::rule_matches;
+use crate::gitignore::parser::Line;
+use crate::gitignore::{Gitignore, LineKind, Rule};
+
+mod hg;
+mod syncthing;
+
+/// Another tool's ignore file to write the rules as
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub(crate) enum Kind {
+    /// .dockerignore, for the files sent to the Docker daemon when building an image
+    Dockerignore,
+    /// .npmignore, for the files left out of an npm package
+    Npmignore,
+    /// .prettierignore, for the files Prettier doesn't format
+    Prettierignore,
+    /// .eslintignore, for the files ESLint doesn't lint
+    Eslintignore,
+    /// .ignore, for the files ripgrep and fd don't search
+    Ignore,
+    /// .hgignore, for the files Mercurial doesn't track, as glob patterns
+    Hgignore,
+    /// .hgignore, for the files Mercurial doesn't track, as regular expressions
+    HgignoreRegexp,
+    /// .stignore, for the files Syncthing doesn't sync
+    Stignore,
+}
+
+impl Kind {
+    pub(crate) fn file_name(&self) -> &'static str {
+        match self {
+            Kind::Dockerignore => ".dockerignore",
+            Kind::Npmignore => ".npmignore",
+            Kind::Prettierignore => ".prettierignore",
+            Kind::Eslintignore => ".eslintignore",
+            Kind::Ignore => ".ignore",
+            Kind::Hgignore | Kind::HgignoreRegexp => ".hgignore",
+            Kind::Stignore => ".stignore",
+        }
+    }
+    // Something worth knowing about the file whatever rules are in it
+    fn note(&self) -> Option<&'static str> {
+        match self {
+            Kind::Eslintignore => Some("ESLint 9 doesn't read .eslintignore unless ESLINT_USE_FLAT_CONFIG is false; with a flat config, put these patterns in the \"ignores\" of eslint.config.js instead."),
+            _ => None,
+        }
+    }
+    // Syncthing comments start with "//"; the rest use "#" like git.
+    fn comment(&self, text: &str) -> String {
+        match self {
+            Kind::Stignore if !text.trim().is_empty() => format!("// {}", text),
+            _ => text.to_string(),
+        }
+    }
+}
+
+/// Something about a rule that the other tool treats differently
+#[derive(Debug)]
+pub(crate) struct Warning {
+    /// The line the rule is on, or None for a warning about the whole file
+    pub(crate) line: Option<usize>,
+    pub(crate) message: String,
+}
+
+/// Rules written as another tool's ignore file
+pub(crate) struct Exported {
+    pub(crate) content: String,
+    pub(crate) warnings: Vec<Warning>,
+    /// How many lines were added at the top, moving the rest down
+    pub(crate) added_lines: usize,
+}
+
+/// Writes .gitignore rules as another tool's ignore file, line for line after anything the tool
+/// needs at the top. A rule the tool can't express is commented out; one it can only come close
+/// to is written as closely as it can be. Either way there's a warning.
+pub(crate) fn export(content: &str, kind: Kind) -> Exported {
+    let gitignore = Gitignore::parse(content);
+    let mut header: Vec<String> = match kind {
+        Kind::Hgignore => vec!["syntax: glob".to_string()],
+        Kind::HgignoreRegexp => vec!["syntax: regexp".to_string()],
+        _ => vec![],
+    };
+    // Syncthing uses the first pattern that matches rather than the last, so re-includes go
+    // first
+    let rules: Vec<(&Line, &Rule)> = gitignore.rules().collect();
+    let reincludes: Vec<String> = rules
+        .iter()
+        .filter(|(_, rule)| kind == Kind::Stignore && rule.negated)
+        .filter_map(|(_, rule)| syncthing::pattern(rule).ok())
+        .map(|(pattern, _)| pattern)
+        .collect();
+    if !reincludes.is_empty() {
+        header.push(
+            "// Syncthing uses the first pattern that matches, so re-includes come first"
+                .to_string(),
+        );
+        header.extend(reincludes);
+        header.push(String::new());
+    }
+    let added_lines = header.len();
+    let ending = gitignore.lines().first().map_or("\n", |line| {
+        if line.ending.is_empty() {
+            "\n"
+        } else {
+            line.ending
+        }
+    });
+    let mut exported: String = header
+        .iter()
+        .map(|line| format!("{}{}", line, ending))
+        .collect();
+    let mut warnings: Vec<Warning> = kind
+        .note()
+        .map(|note| Warning {
+            line: None,
+            message: note.to_string(),
+        })
+        .into_iter()
+        .collect();
+    // Negations that git ignores, because the directory they're in is ignored
+    let ineffective: Vec<(usize, String)> = lint(content)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.code == "ineffective-negation")
+        .map(|diagnostic| (diagnostic.line, diagnostic.message))
+        .collect();
+    for line in gitignore.lines() {
+        let LineKind::Rule(rule) = &line.kind else {
+            exported.push_str(&kind.comment(&line.text));
+            exported.push_str(line.ending);
+            continue;
+        };
+        let text = line.text.as_str();
+        let mut warn = |message: String| {
+            warnings.push(Warning {
+                line: Some(line.number + added_lines),
+                message,
+            })
+        };
+        match translate(text, rule, kind) {
+            Ok((translated, message)) => {
+                if kind == Kind::Stignore && rule.negated {
+                    exported.push_str(
+                        &kind.comment(&format!("{} (moved to the top)", text.trim_end())),
+                    );
+                    // A later rule that git lets ignore the same paths again now comes after it
+                    let later = rules
+                        .iter()
+                        .skip_while(|(other, _)| other.number <= line.number)
+                        .find(|(_, other)| !other.negated && may_overlap(other, rule));
+                    if let Some((later, _)) = later {
+                        warn(format!(
+                            r#"Syncthing uses the first pattern that matches, so this goes before line {} "{}", which git lets ignore some of the same paths again"#,
+                            later.number + added_lines,
+                            later.text.trim_end()
+                        ));
+                    } else if let Some(other) = keeps_ignoring(&rules, rule) {
+                        warn(format!(
+                            r#"Syncthing re-includes what this matches inside a directory it matches or an ignored one, but git still ignores some of it because of line {} "{}""#,
+                            other.number + added_lines,
+                            other.text.trim_end()
+                        ));
+                    }
+                } else {
+                    exported.push_str(&translated);
+                }
+                if let Some(message) = message {
+                    warn(message);
+                }
+                let tool = match kind {
+                    Kind::Dockerignore => Some("Docker"),
+                    Kind::Stignore => Some("Syncthing"),
+                    _ => None,
+                };
+                let blocked = ineffective
+                    .iter()
+                    .find(|(number, _)| *number == line.number);
+                if let (Some(tool), Some((_, message))) = (tool, blocked) {
+                    warn(format!(
+                        "{} re-includes what this matches, but git doesn't, {}",
+                        tool,
+                        message.trim_start_matches("can't re-include anything, ")
+                    ));
+                }
+            }
+            Err(reason) => {
+                exported.push_str(&format!(
+                    "{} {} (not supported in {}: {})",
+                    if kind == Kind::Stignore { "//" } else { "#" },
+                    text.trim_end(),
+                    kind.file_name(),
+                    reason
+                ));
+                warn(format!("commented out, because {}", reason));
+            }
+        }
+        exported.push_str(line.ending);
+    }
+    Exported {
+        content: exported,
+        warnings,
+        added_lines,
+    }
+}
+
+// A rule that git ignores some of what a Syncthing re-include matches by. If the re-include might
+// match a directory, that's any rule, since Syncthing re-includes what's in the directory and git
+// only the directory. Otherwise, it's one that might ignore a directory the re-included paths are
+// in, which git doesn't re-include anything from. Directories with names the lint can check are
+// left to it.
+fn keeps_ignoring<'a>(rules: &[(&'a Line, &'a Rule)], negation: &Rule) -> Option<&'a Line> {
+    let components: Vec<&str> = negation.pattern.split('/').collect();
+    let known_dirs = negation.anchored
+        && components[..components.len() - 1]
+            .iter()
+            .all(|component| !component.contains(['*', '?', '[', '\\']));
+    rules
+        .iter()
+        .filter(|(_, other)| !other.negated)
+        .find(|(_, other)| may_be_dir(negation) || (!known_dirs && may_be_dir(other)))
+        .map(|(line, _)| *line)
+}
+
+// Names without an extension, like "build" or "*", are likely to be directories.
+fn may_be_dir(rule: &Rule) -> bool {
+    let name = rule.pattern.rsplit('/').next().unwrap_or(&rule.pattern);
+    rule.directory_only || !name.contains('.')
+}
+
+// A rule as the other tool would write it, and anything it does differently, or why the tool
+// can't express it. Tools that read .gitignore syntax mostly get `text` as it is.
+fn translate(text: &str, rule: &Rule, kind: Kind) -> Result<(String, Option<String>), String> {
+    if rule.pattern.contains("[:") && kind != Kind::Npmignore {
+        return Err("it uses a character class like [[:alpha:]]".to_string());
+    }
+    match kind {
+        Kind::Dockerignore => {
+            if rule.pattern.ends_with("\\ ") {
+                return Err("Docker trims trailing spaces, even escaped ones".to_string());
+            }
+            // Go negates a character class with "^"
+            let mut pattern = rule.pattern.replace("[!", "[^");
+            // Docker patterns are relative to the build context, so what git matches at any
+            // depth needs a "**/"
+            if !rule.anchored {
+                pattern = format!("**/{}", pattern);
+            }
+            let warning = rule.directory_only.then(|| {
+                "Docker can't match only directories, so this matches files with the same name too"
+                    .to_string()
+            });
+            if rule.negated {
+                pattern = format!("!{}", pattern);
+            }
+            Ok((pattern, warning))
+        }
+        Kind::Npmignore => {
+            let always: Vec<&str> = ["package.json", "README.md", "LICENSE"]
+                .into_iter()
+                .filter(|file| !rule.negated && rule_matches(rule, file, false))
+                .collect();
+            let warning = (!always.is_empty()).then(|| {
+                format!(
+                    "npm always includes {} in a package, whatever this says",
+                    always.join(", ")
+                )
+            });
+            Ok((text.to_string(), warning))
+        }
+        // JavaScript negates a character class with "^", which git also understands
+        Kind::Prettierignore | Kind::Eslintignore => Ok((text.replace("[!", "[^"), None)),
+        Kind::Ignore => Ok((text.to_string(), None)),
+        Kind::Hgignore => hg::glob(rule).map(|glob| (glob, None)),
+        Kind::HgignoreRegexp => hg::regexp(rule).map(|regexp| (regexp, None)),
+        Kind::Stignore => syncthing::pattern(rule),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::{export, Kind};
+    use crate::gitignore::matcher::Matcher;
+    use crate::gitignore::Gitignore;
+    use crate::testing::{self, Random};
+
+    // Rules Mercurial can express. Directories are named a, b and build, and files aren't.
+    const RULES: &[&str] = &[
+        "*.log",
+        "x.log",
+        "build/",
+        "/build/",
+        "a/",
+        "/a/b",
+        "a/*",
+        "**/b",
+        "b/**",
+        "a/**/x.log",
+        "a/**",
+        "**/a/*.txt",
+        "/*.txt",
+        "*",
+        "c.t?t",
+        "*.[lt][ox][gt]",
+        "[!k]*.log",
+        "[^k]*.log",
+        "x{y}.log",
+        "# comment",
+    ];
+    // Re-includes, which only Syncthing can express
+    const NEGATIONS: &[&str] = &["!keep.log", "!a/x.log", "!*.txt", "!b/", "!/build/c.txt"];
+    const DIRS: &[&str] = &["a", "b", "build"];
+    const FILES: &[&str] = &["x.log", "keep.log", "c.txt"];
+
+    fn git_ignored(content: &str, files: &[String]) -> Vec<bool> {
+        let gitignore = Gitignore::parse(content);
+        let matcher = Matcher::new(&gitignore);
+        files
+            .iter()
+            .map(|file| {
+                matcher
+                    .check(file, false)
+                    .is_some_and(|found| found.ignored())
+            })
+            .collect()
+    }
+
+    // The patterns in an exported file, leaving out comments and anything at the top
+    fn patterns(content: &str, comment: &str) -> Vec<String> {
+        content
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with(comment))
+            .filter(|line| !line.starts_with("syntax:"))
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    // Mercurial's _globre, which turns a glob into a regular expression
+    fn hg_globre(pattern: &str) -> String {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut regexp = String::new();
+        let mut group = 0;
+        let mut index = 0;
+        while index < chars.len() {
+            let ch = chars[index];
+            index += 1;
+            match ch {
+                '*' if chars.get(index) == Some(&'*') => {
+                    index += 1;
+                    if chars.get(index) == Some(&'/') {
+                        index += 1;
+                        regexp.push_str("(?:.*/)?");
+                    } else {
+                        regexp.push_str(".*");
+                    }
+                }
+                '*' => regexp.push_str("[^/]*"),
+                '?' => regexp.push('.'),
+                '[' => {
+                    let mut end = index;
+                    if matches!(chars.get(end), Some('!') | Some(']')) {
+                        end += 1;
+                    }
+                    while end < chars.len() && chars[end] != ']' {
+                        end += 1;
+                    }
+                    if end >= chars.len() {
+                        regexp.push_str(r"\[");
+                    } else {
+                        let class: String = chars[index..end].iter().collect();
+                        let class = class.replace('\\', r"\\");
+                        index = end + 1;
+                        let class = match class.strip_prefix('!') {
+                            Some(rest) => format!("^{}", rest),
+                            None if class.starts_with('^') => format!(r"\{}", class),
+                            None => class,
+                        };
+                        regexp.push_str(&format!("[{}]", class));
+                    }
+                }
+                '{' => {
+                    group += 1;
+                    regexp.push_str("(?:");
+                }
+                '}' if group > 0 => {
+                    group -= 1;
+                    regexp.push(')');
+                }
+                ',' if group > 0 => regexp.push('|'),
+                '\\' => match chars.get(index) {
+                    Some(escaped) => {
+                        regexp.push_str(&regex::escape(&escaped.to_string()));
+                        index += 1;
+                    }
+                    None => regexp.push_str(r"\\"),
+                },
+                _ => regexp.push_str(&regex::escape(&ch.to_string())),
+            }
+        }
+        regexp
+    }
+
+    // What Mercurial ignores, with each line of an exported .hgignore as a regular expression
+    fn hg_ignored(regexps: &[String], files: &[String]) -> Vec<bool> {
+        let regexps: Vec<Regex> = regexps
+            .iter()
+            .map(|regexp| Regex::new(regexp).unwrap())
+            .collect();
+        files
+            .iter()
+            .map(|file| regexps.iter().any(|regexp| regexp.is_match(file)))
+            .collect()
+    }
+
+    // A Syncthing glob as a regular expression: "**" matches anything, "*" and "?" don't match
+    // "/", and "[!...]" is the only negated class
+    fn syncthing_regexp(pattern: &str) -> String {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut regexp = String::new();
+        let mut index = 0;
+        while index < chars.len() {
+            let ch = chars[index];
+            index += 1;
+            match ch {
+                '*' if chars.get(index) == Some(&'*') => {
+                    index += 1;
+                    regexp.push_str(".*");
+                }
+                '*' => regexp.push_str("[^/]*"),
+                '?' => regexp.push_str("[^/]"),
+                '[' => {
+                    let end = index + chars[index..].iter().position(|ch| *ch == ']').unwrap();
+                    let class: String = chars[index..end].iter().collect();
+                    index = end + 1;
+                    match class.strip_prefix('!') {
+                        Some(rest) => regexp.push_str(&format!("[^/{}]", regex::escape(rest))),
+                        None => regexp.push_str(&format!("[{}]", regex::escape(&class))),
+                    }
+                }
+                '{' => regexp.push_str("(?:"),
+                '}' => regexp.push(')'),
+                ',' => regexp.push('|'),
+                '\\' => {
+                    regexp.push_str(&regex::escape(&chars[index].to_string()));
+                    index += 1;
+                }
+                _ => regexp.push_str(&regex::escape(&ch.to_string())),
+            }
+        }
+        regexp
+    }
+
+    // What Syncthing ignores: the first pattern that matches a file or a directory it's in
+    // decides. Like Syncthing, a pattern without a leading "/" is tried at the top and with "**/"
+    // in front, and one starting with "**/" is also tried without it.
+    fn syncthing_ignored(patterns: &[String], files: &[String]) -> Vec<bool> {
+        let patterns: Vec<(bool, Regex)> = patterns
+            .iter()
+            .map(|pattern| {
+                let (negated, pattern) = match pattern.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, pattern.as_str()),
+                };
+                let globs = match (pattern.strip_prefix('/'), pattern.strip_prefix("**/")) {
+                    (Some(rooted), _) => vec![rooted.to_string()],
+                    (None, Some(rest)) => vec![pattern.to_string(), rest.to_string()],
+                    (None, None) => vec![pattern.to_string(), format!("**/{}", pattern)],
+                };
+                let regexps: Vec<String> = globs
+                    .iter()
+                    .map(|glob| format!("{}(?:/.*)?", syncthing_regexp(glob)))
+                    .collect();
+                let regexp = format!("^(?:{})$", regexps.join("|"));
+                (negated, Regex::new(&regexp).unwrap())
+            })
+            .collect();
+        files
+            .iter()
+            .map(|file| {
+                patterns
+                    .iter()
+                    .find(|(_, regexp)| regexp.is_match(file))
+                    .is_some_and(|(negated, _)| !negated)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hgignore_globs_ignore_what_git_does() {
+        let files = testing::paths(DIRS, FILES, 2);
+        let mut random = Random(0x0123_4567_89ab_cdef);
+        for _ in 0..300 {
+            let content = random.lines(RULES, 6);
+            let exported = export(&content, Kind::Hgignore);
+            assert!(exported.warnings.is_empty(), "{:?}", exported.warnings);
+            let regexps: Vec<String> = patterns(&exported.content, "#")
+                .iter()
+                .map(|glob| match glob.strip_prefix("rootglob:") {
+                    Some(glob) => format!("^{}(?:/|$)", hg_globre(glob)),
+                    None => format!("^(?:|.*/){}(?:/|$)", hg_globre(glob)),
+                })
+                .collect();
+            assert_eq!(
+                git_ignored(&content, &files),
+                hg_ignored(&regexps, &files),
+                "{:?} exported as {:?}",
+                content,
+                exported.content
+            );
+        }
+    }
+
+    #[test]
+    fn hgignore_regexps_ignore_what_git_does() {
+        let files = testing::paths(DIRS, FILES, 2);
+        let mut random = Random(0xfedc_ba98_7654_3210);
+        for _ in 0..300 {
+            let content = random.lines(RULES, 6);
+            let exported = export(&content, Kind::HgignoreRegexp);
+            assert!(exported.warnings.is_empty(), "{:?}", exported.warnings);
+            assert_eq!(
+                git_ignored(&content, &files),
+                hg_ignored(&patterns(&exported.content, "#"), &files),
+                "{:?} exported as {:?}",
+                content,
+                exported.content
+            );
+        }
+    }
+
+    #[test]
+    fn hgignore_comments_out_negations() {
+        let exported = export("*.log\n!keep.log", Kind::Hgignore);
+        assert_eq!(exported.warnings.len(), 1);
+        assert_eq!(patterns(&exported.content, "#"), ["*.log"]);
+    }
+
+    #[test]
+    fn stignore_ignores_what_git_does() {
+        let files = testing::paths(DIRS, FILES, 2);
+        let pool: Vec<&str> = RULES.iter().chain(NEGATIONS).copied().collect();
+        let mut random = Random(0x0f1e_2d3c_4b5a_6978);
+        let mut compared = 0;
+        for _ in 0..600 {
+            let content = random.lines(&pool, 6);
+            let exported = export(&content, Kind::Stignore);
+            // Directories are never named like files here, so only other warnings mean Syncthing
+            // can differ
+            let differs = exported.warnings.iter().any(|warning| {
+                warning.message
+                    != "Syncthing can't match only directories, so this matches files with the same name too"
+            });
+            if differs {
+                continue;
+            }
+            compared += 1;
+            assert_eq!(
+                git_ignored(&content, &files),
+                syncthing_ignored(&patterns(&exported.content, "//"), &files),
+                "{:?} exported as {:?}",
+                content,
+                exported.content
+            );
+        }
+        assert!(compared > 250, "only {} compared", compared);
+    }
+}
@@ -0,0 +1,184 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-01
+ */
+
+use crate::gitignore::Rule;
+
+// Mercurial has no negation, so a rule that re-includes something can't be written at all.
+fn check(rule: &Rule) -> Result<(), String> {
+    if rule.negated {
+        Err(
+            "Mercurial can't re-include anything, so what this re-includes stays ignored"
+                .to_string(),
+        )
+    } else if rule.pattern.ends_with("\\ ") {
+        Err("Mercurial drops trailing spaces, even escaped ones".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// A rule as a Mercurial glob. Like git, a glob matches at any depth, so an anchored rule uses
+/// "rootglob:" to match from the root instead. Mercurial only tracks files, so a rule for
+/// directories matches what's in them.
+pub(super) fn glob(rule: &Rule) -> Result<String, String> {
+    check(rule)?;
+    let mut glob = hg_glob(&rule.pattern);
+    if rule.directory_only {
+        // A trailing "**" here has to match at least one directory, which "*" does before "/**"
+        if glob == "**" || glob.ends_with("/**") {
+            glob.pop();
+        }
+        glob.push_str("/**");
+    }
+    if rule.anchored {
+        glob = format!("rootglob:{}", glob);
+    }
+    Ok(glob)
+}
+
+/// A rule as a regular expression, which Mercurial looks for anywhere in a path unless it starts
+/// with "^". It matches a path the rule matches or anything in it.
+pub(super) fn regexp(rule: &Rule) -> Result<String, String> {
+    check(rule)?;
+    let start = if rule.anchored { "^" } else { "(?:^|/)" };
+    let end = if rule.directory_only { "/" } else { "(?:/|$)" };
+    Ok(format!("{}{}{}", start, glob_regexp(&rule.pattern), end))
+}
+
+// A git glob as a Mercurial one. In Mercurial "?" and "[!...]" match "/" and "**" always does,
+// braces are alternatives, and "#" starts a comment anywhere in the line.
+fn hg_glob(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut glob = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        index += 1;
+        match ch {
+            '*' if chars.get(index) == Some(&'*') => {
+                index += 1;
+                let at_start = index == 2 || chars[index - 3] == '/';
+                let at_end = matches!(chars.get(index), None | Some('/'));
+                glob.push_str(if at_start && at_end { "**" } else { "*" });
+            }
+            '?' => glob.push_str("[!/]"),
+            '[' => match class_end(&chars[index..]) {
+                Some(length) => {
+                    let class: String = chars[index..index + length].iter().collect();
+                    index += length;
+                    match class.strip_prefix(['!', '^']) {
+                        Some(rest) => glob.push_str(&format!("[!/{}", rest)),
+                        None => glob.push_str(&format!("[{}", class)),
+                    }
+                }
+                None => glob.push_str(r"\["),
+            },
+            '\\' => {
+                glob.push(ch);
+                if let Some(&escaped) = chars.get(index) {
+                    glob.push(escaped);
+                    index += 1;
+                }
+            }
+            '{' | '}' | '#' => {
+                glob.push('\\');
+                glob.push(ch);
+            }
+            _ => glob.push(ch),
+        }
+    }
+    glob
+}
+
+// How many characters a character class takes after its "[", including the "]", or None if
+// there's no "]" to end it.
+fn class_end(chars: &[char]) -> Option<usize> {
+    let mut index = usize::from(matches!(chars.first(), Some('!') | Some('^')));
+    // A "]" straight after the "[" or "[!" is part of the class
+    if chars.get(index) == Some(&']') {
+        index += 1;
+    }
+    let offset = chars[index..].iter().position(|ch| *ch == ']')?;
+    Some(index + offset + 1)
+}
+
+// The regular expression for a git glob, where "*" and "?" don't match "/" but "**" between
+// slashes matches any number of directories.
+fn glob_regexp(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut regexp = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        index += 1;
+        match ch {
+            '*' if chars.get(index) == Some(&'*') => {
+                index += 1;
+                let at_start = index == 2 || chars[index - 3] == '/';
+                match chars.get(index) {
+                    None if at_start => regexp.push_str(".*"),
+                    Some('/') if at_start => {
+                        index += 1;
+                        regexp.push_str("(?:.*/)?");
+                    }
+                    // Anywhere else it's the same as "*"
+                    _ => regexp.push_str("[^/]*"),
+                }
+            }
+            '*' => regexp.push_str("[^/]*"),
+            '?' => regexp.push_str("[^/]"),
+            '[' => match class_regexp(&chars[index..]) {
+                Some((class, length)) => {
+                    regexp.push_str(&class);
+                    index += length;
+                }
+                None => regexp.push_str(r"\["),
+            },
+            '\\' => {
+                if let Some(escaped) = chars.get(index) {
+                    regexp.push_str(&regex::escape(&escaped.to_string()));
+                    index += 1;
+                }
+            }
+            _ => regexp.push_str(&regex::escape(&ch.to_string())),
+        }
+    }
+    regexp
+}
+
+// A character class after its "[", as a regular expression that doesn't match "/", and how many
+// characters it took, or None if there's no "]" to end it.
+fn class_regexp(chars: &[char]) -> Option<(String, usize)> {
+    let mut index = 0;
+    let negated = matches!(chars.first(), Some('!') | Some('^'));
+    if negated {
+        index += 1;
+    }
+    let mut class = String::from(if negated { "[^/" } else { "[" });
+    let start = index;
+    loop {
+        let ch = *chars.get(index)?;
+        index += 1;
+        match ch {
+            ']' if index - 1 > start => break,
+            '-' if index - 1 > start && chars.get(index) != Some(&']') => class.push('-'),
+            '\\' => {
+                let escaped = *chars.get(index)?;
+                index += 1;
+                if !escaped.is_alphanumeric() {
+                    class.push('\\');
+                }
+                class.push(escaped);
+            }
+            _ if ch.is_alphanumeric() => class.push(ch),
+            _ => {
+                class.push('\\');
+                class.push(ch);
+            }
+        }
+    }
+    class.push(']');
+    Some((class, index))
+}
@@ -0,0 +1,73 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-01
+ */
+
+use crate::gitignore::Rule;
+
+/// A rule as a Syncthing pattern. Syncthing matches a pattern without a leading "/" at any
+/// depth, even with a "/" in the middle, so an anchored rule gets one, unless it starts with
+/// "**/", which Syncthing already matches at the top as well.
+pub(super) fn pattern(rule: &Rule) -> Result<(String, Option<String>), String> {
+    if rule.pattern.ends_with("\\ ") {
+        return Err("Syncthing drops trailing spaces, even escaped ones".to_string());
+    }
+    // Braces are alternatives in Syncthing
+    let mut pattern = escape_outside_classes(&rule.pattern, &['{', '}']);
+    if rule.anchored && !pattern.starts_with("**/") {
+        pattern = format!("/{}", pattern);
+    }
+    if rule.negated {
+        pattern = format!("!{}", pattern);
+    }
+    let mut warnings = vec![];
+    if rule.directory_only {
+        warnings.push(
+            "Syncthing can't match only directories, so this matches files with the same name too",
+        );
+    }
+    if rule.pattern.contains("/**/") {
+        warnings.push(
+            r#"Syncthing's "/**/" needs at least one directory in between, so "a/**/b" doesn't match "a/b""#,
+        );
+    }
+    let warning = (!warnings.is_empty()).then(|| warnings.join("; "));
+    Ok((pattern, warning))
+}
+
+// Puts a backslash before each of `special` that isn't escaped already or in a character class,
+// and writes a class negated with "[^" as "[!", the only way Syncthing has.
+fn escape_outside_classes(pattern: &str, special: &[char]) -> String {
+    let mut escaped = String::new();
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                escaped.push(ch);
+                escaped.extend(chars.next());
+                continue;
+            }
+            '[' if !in_class => {
+                in_class = true;
+                escaped.push(ch);
+                // A "]" straight after the "[" or "[!" is part of the class
+                if let Some(&next) = chars.peek() {
+                    if next == '!' || next == '^' {
+                        chars.next();
+                        escaped.push('!');
+                    }
+                }
+                if chars.peek() == Some(&']') {
+                    escaped.extend(chars.next());
+                }
+                continue;
+            }
+            ']' if in_class => in_class = false,
+            _ if !in_class && special.contains(&ch) => escaped.push('\\'),
+            _ => {}
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
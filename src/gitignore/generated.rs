@@ -0,0 +1,186 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-24
+ */
+
+use std::ops::Range;
+
+use crate::gitignore::Gitignore;
+use crate::gitignore_api;
+
+/// Rules as the gitignore.io API generated them, from its "# Created by" line to its "# End of"
+/// line, which both give the URL the templates came from
+pub(crate) struct GeneratedBlock {
+    pub(crate) templates: Vec<String>,
+    /// Indexes of the block's lines, including the first and last
+    pub(crate) lines: Range<usize>,
+}
+
+/// Finds the generated blocks in a .gitignore file. A block without an "# End of" line runs to
+/// the end of the file.
+pub(crate) fn find_blocks(gitignore: &Gitignore) -> Vec<GeneratedBlock> {
+    let lines = gitignore.lines();
+    let mut blocks = vec![];
+    let mut index = 0;
+    while index < lines.len() {
+        let Some(templates) = templates_from_url(&lines[index].text, "# Created by ") else {
+            index += 1;
+            continue;
+        };
+        let end = lines[index + 1..]
+            .iter()
+            .position(|line| templates_from_url(&line.text, "# End of ").is_some())
+            .map_or(lines.len(), |offset| index + 1 + offset + 1);
+        blocks.push(GeneratedBlock {
+            templates,
+            lines: index..end,
+        });
+        index = end;
+    }
+    blocks
+}
+
+// Reads the template names from the end of a line like
+// "# Created by https://www.toptal.com/developers/gitignore/api/rust,node".
+fn templates_from_url(text: &str, prefix: &str) -> Option<Vec<String>> {
+    let url = text.trim_end().strip_prefix(prefix)?;
+    if !url.starts_with("http") {
+        return None;
+    }
+    let (_, list) = url.rsplit_once("/api/")?;
+    let templates: Vec<String> = list
+        .split(',')
+        .filter(|template| !template.is_empty())
+        .map(|template| template.to_string())
+        .collect();
+    if templates.is_empty() {
+        None
+    } else {
+        Some(templates)
+    }
+}
+
+/// The rules of a single template as the API returns them, without the lines around them that
+/// give the URL they came from
+pub(crate) fn template_body(response: &str) -> String {
+    let gitignore = Gitignore::parse(response);
+    let body: Vec<&str> = gitignore
+        .lines()
+        .iter()
+        .map(|line| line.text.as_str())
+        .filter(|text| {
+            !(templates_from_url(text, "# Created by ").is_some()
+                || templates_from_url(text, "# End of ").is_some()
+                || text.starts_with("# Edit at http"))
+        })
+        .collect();
+    body.join("\n").trim_matches('\n').to_string()
+}
+
+/// How to put the rules of several templates together
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub(crate) enum Layout {
+    /// In the order the templates were given, each starting with a "### Name ###" header
+    #[default]
+    Given,
+    /// Exactly as the API returns them when asked for all of them at once
+    Toptal,
+}
+
+impl Layout {
+    /// Whether putting templates together this way changes the order they're given in, which
+    /// the API does by sorting them by name
+    pub(crate) fn reorders(self, template_names: &[String]) -> bool {
+        self == Self::Toptal
+            && template_names
+                .windows(2)
+                .any(|pair| pair[0].to_lowercase() > pair[1].to_lowercase())
+    }
+}
+
+/// Template rules put together the way the API does for several templates at once
+pub(crate) struct Assembled {
+    pub(crate) content: String,
+    /// Which lines of the content each template's rules are on, by index
+    pub(crate) templates: Vec<(String, Range<usize>)>,
+}
+
+/// Puts template rules together the way the API does for several templates at once, so the
+/// result can be checked with `gi-gen check` like any other
+pub(crate) fn assemble(templates: &[(String, String)]) -> Assembled {
+    let names: Vec<String> = templates.iter().map(|(name, _)| name.clone()).collect();
+    let header = [
+        String::new(),
+        format!("# Created by {}", gitignore_api::template_url(&names)),
+        format!("# Edit at {}", gitignore_api::edit_url(&names)),
+        String::new(),
+    ];
+    let mut lines: Vec<&str> = header.iter().map(|line| line.as_str()).collect();
+    let mut ranges = vec![];
+    let headers: Vec<String> = templates
+        .iter()
+        .map(|(name, _)| format!("### {} ###", name))
+        .collect();
+    for ((name, body), header) in templates.iter().zip(&headers) {
+        let start = lines.len();
+        // A template that doesn't start with a header gets one, so each section has one
+        if !body.starts_with("### ") {
+            lines.push(header);
+        }
+        lines.extend(body.split('\n'));
+        ranges.push((name.clone(), start..lines.len()));
+        lines.push("");
+    }
+    let footer = format!("# End of {}", gitignore_api::template_url(&names));
+    lines.push(&footer);
+    Assembled {
+        content: lines.join("\n"),
+        templates: ranges,
+    }
+}
+
+/// Puts templates together exactly as the API does when asked for all of them at once, from
+/// what it returns for each one on its own. The API sorts them by name, whatever order they're
+/// asked for in.
+pub(crate) fn assemble_toptal(responses: &[(String, String)]) -> Assembled {
+    let names: Vec<String> = responses.iter().map(|(name, _)| name.clone()).collect();
+    let mut sorted: Vec<&(String, String)> = responses.iter().collect();
+    sorted.sort_by_key(|(name, _)| name.to_lowercase());
+    let mut content = format!(
+        "\n# Created by {}\n# Edit at {}\n",
+        gitignore_api::template_url(&names),
+        gitignore_api::edit_url(&names)
+    );
+    let mut ranges = vec![];
+    for (name, response) in sorted {
+        let start = content.matches('\n').count();
+        content.push_str(response_sections(response));
+        let end = content.matches('\n').count() + usize::from(!content.ends_with('\n'));
+        ranges.push((name.clone(), start..end));
+    }
+    content.push_str(&format!(
+        "\n# End of {}",
+        gitignore_api::template_url(&names)
+    ));
+    Assembled {
+        content,
+        templates: ranges,
+    }
+}
+
+// The part of a response between the lines that give the URL, exactly as it is
+fn response_sections(response: &str) -> &str {
+    let start = response
+        .find("# Edit at http")
+        .and_then(|index| {
+            response[index..]
+                .find('\n')
+                .map(|offset| index + offset + 1)
+        })
+        .unwrap_or(0);
+    let end = response
+        .rfind("\n# End of http")
+        .filter(|end| *end >= start)
+        .unwrap_or(response.len());
+    &response[start..end]
+}
@@ -0,0 +1,324 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-23
+ */
+
+use crate::gitignore::matcher::{rule_matches, Matcher};
+use crate::gitignore::parser::Line;
+use crate::gitignore::wildmatch::wildmatch;
+use crate::gitignore::{Gitignore, Rule};
+
+/// A problem with one line of a .gitignore file
+#[derive(Debug)]
+pub(crate) struct Diagnostic {
+    pub(crate) line: usize,
+    /// A short name for the kind of problem, like "duplicate"
+    pub(crate) code: &'static str,
+    pub(crate) message: String,
+    /// The line of the other rule the problem is with, if there is one
+    pub(crate) other: Option<usize>,
+}
+
+/// Finds rules that don't do what they seem to: duplicates, rules that broader ones make
+/// pointless, negations that can't re-include anything, and trailing whitespace.
+pub(crate) fn lint(content: &str) -> Vec<Diagnostic> {
+    let gitignore = Gitignore::parse(content);
+    let matcher = Matcher::new(&gitignore);
+    let rules: Vec<(&Line, Rule)> = gitignore
+        .rules()
+        .map(|(line, rule)| (line, rule.normalized()))
+        .collect();
+    let mut diagnostics = vec![];
+    for (index, (line, rule)) in rules.iter().enumerate() {
+        if let Some(message) = trailing_whitespace(line) {
+            diagnostics.push(Diagnostic {
+                line: line.number,
+                code: "trailing-whitespace",
+                message,
+                other: None,
+            });
+        }
+        let diagnostic = covered_by_earlier(&gitignore, &rules, index)
+            .or_else(|| overridden_by_later(&gitignore, &rules, index))
+            .or_else(|| in_ignored_dir(&gitignore, &matcher, line, rule));
+        diagnostics.extend(diagnostic);
+    }
+    diagnostics
+}
+
+// Git drops trailing spaces, but a tab stays part of the pattern.
+fn trailing_whitespace(line: &Line) -> Option<String> {
+    let text = line.text.as_str();
+    if text.ends_with(|ch: char| ch.is_whitespace() && ch != ' ') {
+        Some(
+            "the pattern ends with whitespace other than spaces, which is part of what it matches"
+                .to_string(),
+        )
+    } else if text.ends_with(' ') && !line.rule()?.pattern.ends_with(' ') {
+        Some(r"trailing spaces are ignored; escape the last one with \ if the name ends with a space".to_string())
+    } else {
+        None
+    }
+}
+
+// A rule that an earlier one of the same kind already covers, with nothing in between that could
+// undo the earlier one for the same paths.
+fn covered_by_earlier(
+    gitignore: &Gitignore,
+    rules: &[(&Line, Rule)],
+    index: usize,
+) -> Option<Diagnostic> {
+    let (line, rule) = &rules[index];
+    for (earlier_line, earlier) in rules[..index].iter().rev() {
+        if earlier.negated != rule.negated {
+            if may_overlap(earlier, rule) {
+                return None;
+            }
+            continue;
+        }
+        let code = if earlier == rule {
+            "duplicate"
+        } else if covers(earlier, rule) {
+            "shadowed"
+        } else {
+            continue;
+        };
+        let message = format!(
+            "{} already {} everything this matches",
+            describe(gitignore, line, earlier_line),
+            if rule.negated {
+                "re-includes"
+            } else {
+                "ignores"
+            }
+        );
+        return Some(Diagnostic {
+            line: line.number,
+            code,
+            message,
+            other: Some(earlier_line.number),
+        });
+    }
+    None
+}
+
+// A rule that a later, opposite one undoes for every path it matches.
+fn overridden_by_later(
+    gitignore: &Gitignore,
+    rules: &[(&Line, Rule)],
+    index: usize,
+) -> Option<Diagnostic> {
+    let (line, rule) = &rules[index];
+    let (later_line, later) = rules[index + 1..]
+        .iter()
+        .find(|(_, later)| later.negated != rule.negated && covers(later, rule))?;
+    let message = format!(
+        "has no effect, because {} {} everything it matches",
+        describe(gitignore, line, later_line),
+        if later.negated {
+            "re-includes"
+        } else {
+            "ignores"
+        }
+    );
+    Some(Diagnostic {
+        line: line.number,
+        code: "overridden",
+        message,
+        other: Some(later_line.number),
+    })
+}
+
+// Git doesn't look inside an ignored directory, so a rule for something in one does nothing: a
+// negation can't re-include it, and it's ignored already.
+fn in_ignored_dir(
+    gitignore: &Gitignore,
+    matcher: &Matcher,
+    line: &Line,
+    rule: &Rule,
+) -> Option<Diagnostic> {
+    let dir = parent_dirs(rule).into_iter().find_map(|dir| {
+        let found = matcher.check(&dir, true)?;
+        found.ignored().then_some((dir, found))
+    });
+    let (dir, found) = dir?;
+    let (code, message) = if rule.negated {
+        (
+            "ineffective-negation",
+            format!(
+                "can't re-include anything, because {} ignores the directory {}/ it's in",
+                describe(gitignore, line, found.line),
+                dir
+            ),
+        )
+    } else {
+        (
+            "shadowed",
+            format!(
+                "{} already ignores the directory {}/ that everything this matches is in",
+                describe(gitignore, line, found.line),
+                dir
+            ),
+        )
+    };
+    Some(Diagnostic {
+        line: line.number,
+        code,
+        message,
+        other: Some(found.line.number),
+    })
+}
+
+// Like `line 3 "*.log" (Node)`, naming the other rule's template if it's from a different one.
+fn describe(gitignore: &Gitignore, line: &Line, other: &Line) -> String {
+    let section = gitignore.section_of(line.number);
+    let other_section = gitignore.section_of(other.number);
+    match other_section {
+        Some(name) if other_section != section => format!(
+            r#"line {} "{}" ({})"#,
+            other.number,
+            other.text.trim_end(),
+            name
+        ),
+        _ => format!(r#"line {} "{}""#, other.number, other.text.trim_end()),
+    }
+}
+
+// Whether `broader` matches everything `rule` does, as far as can be told without enumerating
+// paths. It errs towards no. Rules inside an ignored directory are left to `in_ignored_dir`, since
+// whether the directory stays ignored depends on the rest of the file.
+fn covers(broader: &Rule, rule: &Rule) -> bool {
+    if broader.directory_only && !rule.directory_only {
+        return false;
+    }
+    if !has_wildcard(&rule.pattern) {
+        return if rule.anchored {
+            rule_matches(broader, &rule.pattern, true)
+        } else {
+            !broader.anchored && wildmatch(&broader.pattern, &rule.pattern)
+        };
+    }
+    // Otherwise, only the last component can be compared
+    let name = last_component(rule);
+    !broader.anchored && !name.contains("**") && name_glob_covers(&broader.pattern, name)
+}
+
+// Whether two rules might match the same path. They can't if the last component of one is a
+// plain name that the other's last component doesn't match.
+pub(crate) fn may_overlap(rule: &Rule, other: &Rule) -> bool {
+    let name = last_component(rule);
+    let other_name = last_component(other);
+    if name.contains("**") || other_name.contains("**") {
+        return true;
+    }
+    match (has_wildcard(name), has_wildcard(other_name)) {
+        (false, _) => wildmatch(other_name, name),
+        (_, false) => wildmatch(name, other_name),
+        _ => true,
+    }
+}
+
+// Whether one pattern for a single path component matches everything another does.
+fn name_glob_covers(broader: &str, pattern: &str) -> bool {
+    if broader == pattern || broader == "*" {
+        return true;
+    }
+    if let Some(suffix) = broader.strip_prefix('*') {
+        if !has_wildcard(suffix) {
+            return pattern.ends_with(suffix);
+        }
+    }
+    if let Some(prefix) = broader.strip_suffix('*') {
+        if !has_wildcard(prefix) {
+            return pattern.starts_with(prefix);
+        }
+    }
+    false
+}
+
+// The directories that everything an anchored rule matches must be in, as far as the pattern
+// spells them out, like "a" and "a/b" for "a/b/*.c".
+fn parent_dirs(rule: &Rule) -> Vec<String> {
+    if !rule.anchored {
+        return vec![];
+    }
+    let components: Vec<&str> = rule.pattern.split('/').collect();
+    components[..components.len() - 1]
+        .iter()
+        .take_while(|component| !has_wildcard(component))
+        .scan(String::new(), |dir, component| {
+            if !dir.is_empty() {
+                dir.push('/');
+            }
+            dir.push_str(component);
+            Some(dir.clone())
+        })
+        .collect()
+}
+
+fn last_component(rule: &Rule) -> &str {
+    rule.pattern.rsplit('/').next().unwrap_or(&rule.pattern)
+}
+
+fn has_wildcard(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '\\'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lint;
+
+    // Each diagnostic's line and code
+    fn codes(content: &str) -> Vec<(usize, &'static str)> {
+        lint(content)
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.code))
+            .collect()
+    }
+
+    #[test]
+    fn finds_duplicates() {
+        assert_eq!(codes("*.log\n/target\n*.log\n"), [(3, "duplicate")]);
+        assert_eq!(codes("*.log\n**/*.log\n"), [(2, "duplicate")]);
+        let diagnostics = lint("### Node ###\n*.log\n### Python ###\n*.log\n");
+        assert_eq!(diagnostics[0].other, Some(2));
+        assert!(diagnostics[0].message.contains("(Node)"));
+        // The later "*.log" puts back what the negation took out, so it isn't a duplicate, but
+        // the negation does nothing
+        assert_eq!(codes("*.log\n!debug.log\n*.log\n"), [(2, "overridden")]);
+        assert_eq!(codes("/*.log\n*.log\nlogs/\nlogs\n"), []);
+    }
+
+    #[test]
+    fn finds_shadowed_rules() {
+        assert_eq!(codes("*.log\ndebug.log\n"), [(2, "shadowed")]);
+        assert_eq!(codes("/build/\n/build/out.txt\n"), [(2, "shadowed")]);
+        assert_eq!(codes("debug.log\n*.log\n"), []);
+        // Only the directory is ignored, not a file with its name
+        assert_eq!(codes("build/\nbuild\n"), []);
+    }
+
+    #[test]
+    fn finds_overridden_rules() {
+        assert_eq!(codes("debug.log\n!*.log\n"), [(1, "overridden")]);
+        assert_eq!(codes("!*.log\ndebug.log\n"), []);
+    }
+
+    #[test]
+    fn finds_ineffective_negations() {
+        assert_eq!(
+            codes("/build/\n!/build/keep.txt\n"),
+            [(2, "ineffective-negation")]
+        );
+        // What's in the directory is ignored, but not the directory itself
+        assert_eq!(codes("/build/*\n!/build/keep.txt\n"), []);
+    }
+
+    #[test]
+    fn finds_trailing_whitespace() {
+        assert_eq!(codes("*.log  \n"), [(1, "trailing-whitespace")]);
+        assert_eq!(codes("*.log\t\n"), [(1, "trailing-whitespace")]);
+        // An escaped space is part of the name
+        assert_eq!(codes("name\\ \n*.log\n"), []);
+    }
+}
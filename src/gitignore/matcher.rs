@@ -0,0 +1,201 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-20
+ */
+
+use crate::gitignore::parser::Line;
+use crate::gitignore::rule::Rule;
+use crate::gitignore::wildmatch::wildmatch;
+use crate::gitignore::Gitignore;
+
+/// The rule that decides whether a path is ignored
+pub(crate) struct Match<'a> {
+    pub(crate) line: &'a Line,
+    pub(crate) rule: &'a Rule,
+    /// What the rule matched: the path itself, or a directory it's in
+    pub(crate) path: String,
+}
+
+impl Match<'_> {
+    pub(crate) fn ignored(&self) -> bool {
+        !self.rule.negated
+    }
+}
+
+/// Checks paths against the rules of a .gitignore file, as git would. Paths are relative to the
+/// directory the file applies to, with "/" between components.
+pub(crate) struct Matcher<'a> {
+    gitignore: &'a Gitignore,
+}
+
+impl<'a> Matcher<'a> {
+    pub(crate) fn new(gitignore: &'a Gitignore) -> Self {
+        Self { gitignore }
+    }
+
+    /// Finds the rule that decides whether `path` is ignored, if any rule matches it.
+    ///
+    /// Git doesn't look inside a directory that's ignored, so nothing in it can be re-included;
+    /// the rule that ignores the closest such directory decides instead.
+    pub(crate) fn check(&self, path: &str, is_dir: bool) -> Option<Match<'a>> {
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        for end in 1..components.len() {
+            let dir = components[..end].join("/");
+            if let Some(found) = self.last_match(&dir, true) {
+                if found.ignored() {
+                    return Some(found);
+                }
+            }
+        }
+        self.last_match(&components.join("/"), is_dir)
+    }
+
+    // The last rule wins.
+    fn last_match(&self, path: &str, is_dir: bool) -> Option<Match<'a>> {
+        let gitignore: &'a Gitignore = self.gitignore;
+        gitignore
+            .rules()
+            .filter(|(_, rule)| rule_matches(rule, path, is_dir))
+            .last()
+            .map(|(line, rule)| Match {
+                line,
+                rule,
+                path: path.to_string(),
+            })
+    }
+}
+
+/// Whether one rule matches a path on its own. A pattern without a "/" matches the last
+/// component at any depth; one with a "/" matches the whole path.
+pub(crate) fn rule_matches(rule: &Rule, path: &str, is_dir: bool) -> bool {
+    if rule.directory_only && !is_dir {
+        return false;
+    }
+    if rule.anchored {
+        // Git compares the part before the first wildcard on its own and matches the rest
+        // against what's left, so a "**" right after it acts as if it starts a component.
+        let literal = rule
+            .pattern
+            .find(['*', '?', '[', '\\'])
+            .unwrap_or(rule.pattern.len());
+        let (prefix, rest) = rule.pattern.split_at(literal);
+        match path.strip_prefix(prefix) {
+            Some(remainder) => wildmatch(rest, remainder),
+            None => false,
+        }
+    } else {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        wildmatch(&rule.pattern, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Matcher;
+    use crate::gitignore::Gitignore;
+
+    // Rules, a path, whether it's a directory, and whether git ignores it, after git's
+    // t0008-ignores and its documentation of the pattern format
+    const CASES: &[(&str, &str, bool, bool)] = &[
+        // A pattern without a slash matches at any depth
+        ("foo", "foo", false, true),
+        ("foo", "a/b/foo", false, true),
+        ("foo", "a/foo", true, true),
+        ("foo", "foobar", false, false),
+        ("*.log", "logs/debug.log", false, true),
+        ("*.log", "logs/debug.log.old", false, false),
+        // A leading or middle slash anchors it to the directory of the .gitignore file
+        ("/foo", "foo", false, true),
+        ("/foo", "a/foo", false, false),
+        ("a/b", "a/b", false, true),
+        ("a/b", "x/a/b", false, false),
+        ("doc/*.txt", "doc/notes.txt", false, true),
+        ("doc/*.txt", "doc/server/arch.txt", false, false),
+        ("/*.c", "cat-file.c", false, true),
+        ("/*.c", "mozilla-sha1/sha1.c", false, false),
+        // "**"
+        ("**/foo", "foo", false, true),
+        ("**/foo", "a/b/foo", false, true),
+        ("**/foo/bar", "x/foo/bar", false, true),
+        ("**/foo/bar", "foo/bar", false, true),
+        ("abc/**", "abc/x", false, true),
+        ("abc/**", "abc/x/y", false, true),
+        ("abc/**", "abc", true, false),
+        ("a/**/b", "a/b", false, true),
+        ("a/**/b", "a/x/b", false, true),
+        ("a/**/b", "a/x/y/b", false, true),
+        ("a/**/b", "b", false, false),
+        // A trailing slash only matches directories, and what's in them
+        ("build/", "build", true, true),
+        ("build/", "build", false, false),
+        ("build/", "build/out.o", false, true),
+        ("build/", "src/build", true, true),
+        ("build/", "src/build/x/y", false, true),
+        ("/build/", "src/build", true, false),
+        ("frotz/", "a/frotz/b", false, true),
+        // The last rule that matches wins, and "!" re-includes
+        ("*.log\n!important.log", "important.log", false, false),
+        ("*.log\n!important.log", "other.log", false, true),
+        ("!important.log\n*.log", "important.log", false, true),
+        ("/*\n!/foo\n/foo/*\n!/foo/bar", "foo/bar", true, false),
+        ("/*\n!/foo\n/foo/*\n!/foo/bar", "foo/baz", false, true),
+        ("/*\n!/foo\n/foo/*\n!/foo/bar", "other", false, true),
+        // Nothing in an excluded directory can be re-included
+        ("dir/\n!dir/keep", "dir/keep", false, true),
+        ("dir\n!dir/keep", "dir/keep", false, true),
+        ("dir/\n!keep", "dir/keep", false, true),
+        ("dir/*\n!dir/keep", "dir/keep", false, false),
+        ("dir/*\n!dir/keep", "dir/other", false, true),
+        (
+            "dir/**\n!dir/sub/\n!dir/sub/keep",
+            "dir/sub/keep",
+            false,
+            false,
+        ),
+        ("dir/\n!dir/", "dir/file", false, false),
+        // Escapes
+        ("#foo", "#foo", false, false),
+        ("\\#foo", "#foo", false, true),
+        ("!bar", "!bar", false, false),
+        ("\\!bar", "!bar", false, true),
+        ("trail ", "trail", false, true),
+        ("trail ", "trail ", false, false),
+        ("trail\\ ", "trail ", false, true),
+        ("trail\\ ", "trail", false, false),
+        ("trail\\  ", "trail ", false, true),
+        ("\\*star", "*star", false, true),
+        ("\\*star", "xstar", false, false),
+        ("a\\?", "a?", false, true),
+        ("a\\?", "ab", false, false),
+        // Brackets and POSIX classes
+        ("*.py[cod]", "module.pyc", false, true),
+        ("*.py[cod]", "module.py", false, false),
+        ("*.py[!cod]", "module.pyx", false, true),
+        ("*.py[!cod]", "module.pyc", false, false),
+        ("[[:digit:]]*.txt", "1a.txt", false, true),
+        ("[[:digit:]]*.txt", "a1.txt", false, false),
+        ("[[:upper:]][[:lower:]]*", "Makefile", false, true),
+        ("[[:upper:]][[:lower:]]*", "makefile", false, false),
+        ("file[0-9]", "file7", false, true),
+        ("file[0-9]", "filex", false, false),
+        ("a[/]b", "a/b", false, false),
+        // Trailing spaces are dropped, but tabs aren't
+        ("\t\n", "\t", false, true),
+        ("   \n", " ", false, false),
+    ];
+
+    #[test]
+    fn ignores_like_git() {
+        for (rules, path, is_dir, expected) in CASES {
+            let gitignore = Gitignore::parse(rules);
+            let ignored = Matcher::new(&gitignore)
+                .check(path, *is_dir)
+                .is_some_and(|found| found.ignored());
+            assert_eq!(
+                ignored, *expected,
+                "{:?} against {:?} (directory: {})",
+                rules, path, is_dir
+            );
+        }
+    }
+}
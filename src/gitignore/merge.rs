@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-08
+ */
+
+use std::collections::HashSet;
+
+use crate::gitignore::{Gitignore, LineKind, Rule};
+
+/// Adds the rules in `new` that aren't already in `existing`, leaving the existing content, its
+/// order and its comments as they are.
+///
+/// Rules are the same if they match the same paths, whatever their text, so "**/foo" is already
+/// there if "foo" is, but "foo\ " and " foo" aren't.
+///
+/// A section header from `new` comes along with the first rule it introduces, so it's still clear
+/// where the added rules came from.
+pub(crate) fn merge(existing: &str, new: &str) -> String {
+    let existing_gitignore = Gitignore::parse(existing);
+    let new_gitignore = Gitignore::parse(new);
+    let mut present: HashSet<Rule> = existing_gitignore
+        .rules()
+        .map(|(_, rule)| rule.normalized())
+        .collect();
+    let mut added = String::new();
+    let mut pending_header: Option<&str> = None;
+    for line in new_gitignore.lines() {
+        match &line.kind {
+            LineKind::SectionHeader(_) => pending_header = Some(line.text.as_str()),
+            LineKind::Rule(rule) if present.insert(rule.normalized()) => {
+                if let Some(header) = pending_header.take() {
+                    if !added.is_empty() {
+                        added.push('\n');
+                    }
+                    added.push_str(header);
+                    added.push('\n');
+                }
+                added.push_str(line.text.as_str());
+                added.push('\n');
+            }
+            _ => { /* comments, blank lines and rules that are already there */ }
+        }
+    }
+
+    let mut merged = existing.to_string();
+    if !added.is_empty() {
+        if !merged.is_empty() {
+            if !merged.ends_with('\n') {
+                merged.push('\n');
+            }
+            merged.push('\n');
+        }
+        merged.push_str(added.as_str());
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge;
+
+    #[test]
+    fn adds_new_rules_after_the_existing_ones_in_order() {
+        assert_eq!(merge("", "b\na\n"), "b\na\n");
+        assert_eq!(merge("a\n", "c\na\nb\n"), "a\n\nc\nb\n");
+        assert_eq!(merge("a", "b"), "a\n\nb\n");
+        assert_eq!(merge("a\n", "a\n"), "a\n");
+    }
+
+    #[test]
+    fn leaves_the_existing_content_alone() {
+        let existing = "# keep this\r\n\r\n/target  \r\n!keep.log\r\n";
+        assert_eq!(merge(existing, "# new comment\n/target\n"), existing);
+        assert_eq!(
+            merge(existing, "# new comment\n\n*.log\n"),
+            format!("{}\n*.log\n", existing)
+        );
+    }
+
+    #[test]
+    fn brings_a_section_header_with_its_first_new_rule() {
+        let existing = "### Rust ###\n/target\n";
+        let new = "### Rust ###\n/target\n\n### Node ###\n# Logs\nnode_modules/\n*.log\n";
+        assert_eq!(
+            merge(existing, new),
+            "### Rust ###\n/target\n\n### Node ###\nnode_modules/\n*.log\n"
+        );
+        // Nothing new under a header leaves it out
+        assert_eq!(merge(existing, "### Rust ###\n/target\n"), existing);
+        assert_eq!(
+            merge("", "### A ###\na\n### B ###\nb\n"),
+            "### A ###\na\n\n### B ###\nb\n"
+        );
+    }
+
+    #[test]
+    fn compares_rules_by_what_they_match() {
+        // The same rules written differently
+        assert_eq!(merge("foo\n", "**/foo\n"), "foo\n");
+        assert_eq!(merge("foo\n", "foo   \n"), "foo\n");
+        // Different rules with the same text once trimmed
+        assert_eq!(merge("foo\n", "foo\\ \n"), "foo\n\nfoo\\ \n");
+        assert_eq!(merge("foo\n", " foo\n"), "foo\n\n foo\n");
+        assert_eq!(
+            merge("foo\n", "/foo\n!foo\nfoo/\n"),
+            "foo\n\n/foo\n!foo\nfoo/\n"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-30
+ */
+
+use std::ops::Range;
+
+use crate::gitignore::lint::{lint, Diagnostic};
+use crate::gitignore::Gitignore;
+
+// The lint problems that mean a rule can go without changing what's ignored
+const REDUNDANT: [&str; 4] = [
+    "duplicate",
+    "shadowed",
+    "overridden",
+    "ineffective-negation",
+];
+
+/// A rule that was dropped, and why
+#[derive(Debug)]
+pub(crate) struct Dropped {
+    pub(crate) line: usize,
+    pub(crate) text: String,
+    pub(crate) reason: String,
+}
+
+/// Drops rules that can't change what's ignored: copies of earlier rules, rules a broader one
+/// already covers, rules a later one undoes and negations inside ignored directories. A rule is
+/// only dropped if the rule that makes it redundant stays, so the rest of the file is unchanged.
+/// Line numbers, including those in the reasons, are those of `content`.
+pub(crate) fn minimize(content: &str) -> (String, Vec<Dropped>) {
+    let gitignore = Gitignore::parse(content);
+    let lines = gitignore.lines();
+    // Dropped rules are blanked until the end, so line numbers stay the same
+    let mut working: Vec<String> = lines
+        .iter()
+        .map(|line| format!("{}{}", line.text, line.ending))
+        .collect();
+    let mut dropped: Vec<Dropped> = vec![];
+    loop {
+        let candidates: Vec<Diagnostic> = lint(&working.concat())
+            .into_iter()
+            .filter(|diagnostic| REDUNDANT.contains(&diagnostic.code))
+            .collect();
+        // A rule that makes another redundant can't go at the same time, or neither might be
+        let droppable: Vec<&Diagnostic> = candidates
+            .iter()
+            .filter(|diagnostic| {
+                diagnostic
+                    .other
+                    .is_none_or(|other| !candidates.iter().any(|candidate| candidate.line == other))
+            })
+            .collect();
+        if droppable.is_empty() {
+            break;
+        }
+        for diagnostic in droppable {
+            let line = &lines[diagnostic.line - 1];
+            working[diagnostic.line - 1] = line.ending.to_string();
+            dropped.push(Dropped {
+                line: diagnostic.line,
+                text: line.text.trim_end().to_string(),
+                reason: diagnostic.message.clone(),
+            });
+        }
+    }
+    dropped.sort_by_key(|dropped| dropped.line);
+    let minimized = lines
+        .iter()
+        .filter(|line| !dropped.iter().any(|dropped| dropped.line == line.number))
+        .map(|line| format!("{}{}", line.text, line.ending))
+        .collect();
+    (minimized, dropped)
+}
+
+/// Moves each template's line range to where its lines are once the dropped rules are gone.
+pub(crate) fn remap(templates: &mut [(String, Range<usize>)], dropped: &[Dropped]) {
+    let shift = |index: usize| {
+        dropped
+            .iter()
+            .filter(|dropped| dropped.line <= index)
+            .count()
+    };
+    for (_, range) in templates {
+        *range = range.start - shift(range.start)..range.end - shift(range.end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::minimize;
+    use crate::gitignore::matcher::Matcher;
+    use crate::gitignore::Gitignore;
+    use crate::testing::{self, Random};
+
+    // Rules to build files from, with negated, anchored and directory-only ones
+    const RULES: &[&str] = &[
+        "*.log",
+        "!keep.log",
+        "!*.log",
+        "x.log",
+        "build/",
+        "/build",
+        "build",
+        "!build/",
+        "a/",
+        "/a/",
+        "a/*",
+        "!a/b",
+        "!a/build",
+        "/a/b",
+        "a/b/",
+        "**/b",
+        "b/**",
+        "a/**/x.log",
+        "/*",
+        "!/a",
+        "*",
+        "!*/",
+        "# comment",
+        "",
+    ];
+    const NAMES: &[&str] = &["a", "b", "build", "x.log", "keep.log"];
+
+    fn ignored(content: &str, paths: &[String]) -> Vec<bool> {
+        let gitignore = Gitignore::parse(content);
+        let matcher = Matcher::new(&gitignore);
+        paths
+            .iter()
+            .flat_map(|path| [false, true].map(|is_dir| (path, is_dir)))
+            .map(|(path, is_dir)| {
+                matcher
+                    .check(path, is_dir)
+                    .is_some_and(|found| found.ignored())
+            })
+            .collect()
+    }
+
+    fn assert_unchanged(content: &str, paths: &[String]) {
+        let (minimized, dropped) = minimize(content);
+        assert_eq!(
+            ignored(content, paths),
+            ignored(&minimized, paths),
+            "minimizing {:?} to {:?} (dropped {:?}) changed what's ignored",
+            content,
+            minimized,
+            dropped
+        );
+    }
+
+    #[test]
+    fn keeps_what_is_ignored() {
+        let paths = testing::paths(NAMES, NAMES, 2);
+        let mut random = Random(0x5eed_1234_abcd_ef01);
+        for _ in 0..300 {
+            let content = random.lines(RULES, 8);
+            assert_unchanged(&content, &paths);
+        }
+    }
+
+    #[test]
+    fn drops_redundant_rules() {
+        let paths = testing::paths(NAMES, NAMES, 2);
+        let cases = [
+            ("*.log\n*.log", 1),
+            ("*.log\nx.log", 1),
+            ("build/\n!build/x.log", 1),
+            ("x.log\n!x.log\nx.log", 2),
+        ];
+        for (content, expected) in cases {
+            let (_, dropped) = minimize(content);
+            assert_eq!(dropped.len(), expected, "{:?}: {:?}", content, dropped);
+            assert_unchanged(content, &paths);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-15
+ */
+
+use std::fmt::{Display, Formatter};
+
+use crate::gitignore::rule::Rule;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum LineKind {
+    Blank,
+    Comment,
+    /// A comment like "### Rust ###", which the gitignore.io API puts at the start of each
+    /// template
+    SectionHeader(String),
+    Rule(Rule),
+}
+
+/// One line of a .gitignore file, with enough kept to write it back exactly as it was
+#[derive(Clone, Debug)]
+pub(crate) struct Line {
+    /// The line number in the file, starting at 1
+    pub(crate) number: usize,
+    /// The text of the line, without its line ending
+    pub(crate) text: String,
+    /// "\n", "\r\n", or "" for a last line without one
+    pub(crate) ending: &'static str,
+    pub(crate) kind: LineKind,
+}
+
+impl Line {
+    pub(crate) fn rule(&self) -> Option<&Rule> {
+        match &self.kind {
+            LineKind::Rule(rule) => Some(rule),
+            _ => None,
+        }
+    }
+}
+
+/// The lines of a .gitignore file that come under one section header, or before the first one
+pub(crate) struct Section<'a> {
+    pub(crate) name: Option<&'a str>,
+    pub(crate) lines: &'a [Line],
+}
+
+impl Section<'_> {
+    pub(crate) fn rules(&self) -> impl Iterator<Item = (&Line, &Rule)> {
+        self.lines
+            .iter()
+            .filter_map(|line| line.rule().map(|rule| (line, rule)))
+    }
+    pub(crate) fn rule_count(&self) -> usize {
+        self.rules().count()
+    }
+}
+
+/// A .gitignore file, parsed into lines that can be written back out unchanged
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Gitignore {
+    lines: Vec<Line>,
+}
+
+impl Gitignore {
+    pub(crate) fn parse(text: &str) -> Self {
+        let mut lines = vec![];
+        let mut rest = text;
+        while !rest.is_empty() {
+            let (line, ending, remainder) = match rest.find('\n') {
+                Some(index) if rest[..index].ends_with('\r') => {
+                    (&rest[..index - 1], "\r\n", &rest[index + 1..])
+                }
+                Some(index) => (&rest[..index], "\n", &rest[index + 1..]),
+                None => (rest, "", ""),
+            };
+            lines.push(Line {
+                number: lines.len() + 1,
+                text: line.to_string(),
+                ending,
+                kind: Self::line_kind(line),
+            });
+            rest = remainder;
+        }
+        Self { lines }
+    }
+    pub(crate) fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+    pub(crate) fn rules(&self) -> impl Iterator<Item = (&Line, &Rule)> {
+        self.lines
+            .iter()
+            .filter_map(|line| line.rule().map(|rule| (line, rule)))
+    }
+    pub(crate) fn sections(&self) -> Vec<Section<'_>> {
+        let mut sections = vec![];
+        let mut start = 0;
+        let mut name = None;
+        for (index, line) in self.lines.iter().enumerate() {
+            if let LineKind::SectionHeader(header) = &line.kind {
+                if index > start {
+                    sections.push(Section {
+                        name,
+                        lines: &self.lines[start..index],
+                    });
+                }
+                start = index;
+                name = Some(header.as_str());
+            }
+        }
+        if start < self.lines.len() {
+            sections.push(Section {
+                name,
+                lines: &self.lines[start..],
+            });
+        }
+        sections
+    }
+    /// The name of the section a line is in, if it's in one
+    pub(crate) fn section_of(&self, number: usize) -> Option<&str> {
+        self.sections()
+            .into_iter()
+            .find(|section| section.lines.iter().any(|line| line.number == number))
+            .and_then(|section| section.name)
+    }
+}
+impl Gitignore {
+    fn line_kind(text: &str) -> LineKind {
+        // Git drops trailing spaces, but not tabs, so a line of tabs is a pattern
+        if text.trim_end_matches(' ').is_empty() {
+            LineKind::Blank
+        } else if let Some(name) = Self::section_name(text) {
+            LineKind::SectionHeader(name.to_string())
+        } else if text.starts_with('#') {
+            LineKind::Comment
+        } else {
+            match Rule::parse(text) {
+                Some(rule) => LineKind::Rule(rule),
+                // Something like a lone "!" or "/" doesn't match anything
+                None => LineKind::Comment,
+            }
+        }
+    }
+    fn section_name(text: &str) -> Option<&str> {
+        let name = text.trim_end().strip_prefix("### ")?.strip_suffix(" ###")?;
+        if name.trim().is_empty() {
+            None
+        } else {
+            Some(name.trim())
+        }
+    }
+}
+impl Display for Gitignore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            write!(f, "{}{}", line.text, line.ending)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gitignore, LineKind, Section};
+
+    // Each line's text and ending
+    fn split(text: &str) -> Vec<(String, &'static str)> {
+        Gitignore::parse(text)
+            .lines()
+            .iter()
+            .map(|line| (line.text.clone(), line.ending))
+            .collect()
+    }
+
+    #[test]
+    fn writes_back_what_it_read() {
+        for text in [
+            "",
+            "\n",
+            "*.log\n",
+            "*.log",
+            "*.log\r\n/target\r\n",
+            "*.log\r\n/target",
+            "*.log\nbuild/\r\n\n# comment\r\n",
+            "a\rb\n",
+            "a\r",
+            "\r",
+            "\r\r\n",
+            "trail\\ \n  lead\n\t\n",
+            "### Rust ###\r\n/target/\r\n\r\n",
+        ] {
+            assert_eq!(Gitignore::parse(text).to_string(), text, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn keeps_each_line_ending() {
+        assert_eq!(
+            split("*.log\r\n/target\nbuild/"),
+            [
+                ("*.log".to_string(), "\r\n"),
+                ("/target".to_string(), "\n"),
+                ("build/".to_string(), ""),
+            ]
+        );
+        assert_eq!(
+            split("\n\r\n"),
+            [(String::new(), "\n"), (String::new(), "\r\n")]
+        );
+    }
+
+    #[test]
+    fn keeps_a_lone_carriage_return_in_the_text() {
+        assert_eq!(split("a\rb\n"), [("a\rb".to_string(), "\n")]);
+        assert_eq!(split("a\r"), [("a\r".to_string(), "")]);
+        assert_eq!(split("\r\r\n"), [("\r".to_string(), "\r\n")]);
+    }
+
+    #[test]
+    fn numbers_and_classifies_lines() {
+        let gitignore = Gitignore::parse("### Rust ###\r\n# comment\r\n\r\n/target/\r\n!\r\n");
+        let kinds: Vec<(usize, &LineKind)> = gitignore
+            .lines()
+            .iter()
+            .map(|line| (line.number, &line.kind))
+            .collect();
+        assert!(matches!(kinds[0], (1, LineKind::SectionHeader(name)) if name == "Rust"));
+        assert!(matches!(kinds[1], (2, LineKind::Comment)));
+        assert!(matches!(kinds[2], (3, LineKind::Blank)));
+        assert!(matches!(kinds[3], (4, LineKind::Rule(rule)) if rule.pattern == "target"));
+        // A lone "!" doesn't match anything
+        assert!(matches!(kinds[4], (5, LineKind::Comment)));
+        assert_eq!(gitignore.section_of(4), Some("Rust"));
+    }
+
+    #[test]
+    fn only_blank_or_space_lines_are_blank() {
+        let gitignore = Gitignore::parse("\n   \n\t\n \t \n\\ \n");
+        let kinds: Vec<&LineKind> = gitignore.lines().iter().map(|line| &line.kind).collect();
+        assert!(matches!(kinds[0], LineKind::Blank));
+        assert!(matches!(kinds[1], LineKind::Blank));
+        assert!(matches!(kinds[2], LineKind::Rule(rule) if rule.pattern == "\t"));
+        assert!(matches!(kinds[3], LineKind::Rule(rule) if rule.pattern == " \t"));
+        assert!(matches!(kinds[4], LineKind::Rule(rule) if rule.pattern == "\\ "));
+    }
+
+    #[test]
+    fn splits_into_sections() {
+        let gitignore = Gitignore::parse(
+            "# Created by hand\n.env\n\n### Rust ###\n/target/\n# lock\nCargo.lock\n\n### Node ###\n",
+        );
+        let sections: Vec<(Option<&str>, usize, usize)> = gitignore
+            .sections()
+            .iter()
+            .map(|section| (section.name, section.lines.len(), section.rule_count()))
+            .collect();
+        assert_eq!(
+            sections,
+            [(None, 3, 1), (Some("Rust"), 5, 2), (Some("Node"), 1, 0)]
+        );
+        let total: usize = gitignore.sections().iter().map(Section::rule_count).sum();
+        assert_eq!(total, gitignore.rules().count());
+        assert_eq!(gitignore.section_of(2), None);
+        assert_eq!(gitignore.section_of(4), Some("Rust"));
+        assert_eq!(gitignore.section_of(7), Some("Rust"));
+        assert_eq!(gitignore.section_of(9), Some("Node"));
+        assert!(Gitignore::parse("").sections().is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-15
+ */
+
+/// A pattern line from a .gitignore file, broken down the way git reads it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Rule {
+    /// The pattern without the "!", the leading "/" or the trailing "/", and without trailing
+    /// spaces unless they're escaped. Any other backslash escapes are left for the matcher.
+    pub(crate) pattern: String,
+    /// Starts with "!", so it re-includes what an earlier rule excluded
+    pub(crate) negated: bool,
+    /// Ends with "/", so it only matches directories
+    pub(crate) directory_only: bool,
+    /// Has a "/" at the start or in the middle, so it matches relative to the directory the
+    /// .gitignore file is in rather than at any depth
+    pub(crate) anchored: bool,
+}
+
+impl Rule {
+    /// Parses the text of a line, returning None if it's blank or a comment.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        if text.starts_with('#') {
+            return None;
+        }
+        let text = trim_trailing_spaces(text);
+        if text.is_empty() {
+            return None;
+        }
+        let (negated, text) = match text.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (directory_only, text) = match text.strip_suffix('/') {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, text),
+        };
+        let anchored = text.contains('/');
+        let pattern = text.strip_prefix('/').unwrap_or(text);
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Self {
+            pattern: pattern.to_string(),
+            negated,
+            directory_only,
+            anchored,
+        })
+    }
+    /// The same rule written the simplest way, so rules that match the same paths are equal.
+    /// A leading "**/" matches in any directory, which is what a pattern without a "/" does
+    /// anyway.
+    pub(crate) fn normalized(&self) -> Self {
+        match self.pattern.strip_prefix("**/") {
+            Some(rest) if !rest.contains('/') => Self {
+                pattern: rest.to_string(),
+                anchored: false,
+                ..self.clone()
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+// Git ignores trailing spaces, unless they're escaped with a backslash.
+fn trim_trailing_spaces(text: &str) -> &str {
+    let trimmed = text.trim_end_matches(' ');
+    if trimmed.len() < text.len() && trimmed.ends_with('\\') {
+        let backslashes = trimmed.chars().rev().take_while(|ch| *ch == '\\').count();
+        if backslashes % 2 == 1 {
+            // Keep the escaped space
+            return &text[..trimmed.len() + 1];
+        }
+    }
+    trimmed
+}
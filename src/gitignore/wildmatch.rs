@@ -0,0 +1,427 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-20
+ */
+
+// A port of git's wildmatch.c, with the WM_PATHNAME flag git uses for ignore rules: "*", "?" and
+// "[...]" don't match "/", and "**" between slashes matches any number of directories.
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Match,
+    NoMatch,
+    // Nothing later in the text can match, so give up entirely
+    AbortAll,
+    // Only a "**" further out can still match
+    AbortToStarStar,
+}
+
+/// Matches a gitignore pattern against a path, the way git does.
+pub(crate) fn wildmatch(pattern: &str, text: &str) -> bool {
+    dowild(pattern.as_bytes(), text.as_bytes()) == Outcome::Match
+}
+
+// Reads a byte, or 0 past the end, as the C original reads the terminating NUL.
+fn at(bytes: &[u8], index: usize) -> u8 {
+    bytes.get(index).copied().unwrap_or(0)
+}
+
+fn dowild(pattern: &[u8], text: &[u8]) -> Outcome {
+    let mut p = 0;
+    let mut t = 0;
+    while p < pattern.len() {
+        let p_ch = pattern[p];
+        let t_ch = at(text, t);
+        if t_ch == 0 && p_ch != b'*' {
+            return Outcome::AbortAll;
+        }
+        match p_ch {
+            b'\\' => {
+                // Literal match of the next character
+                p += 1;
+                if t_ch != at(pattern, p) {
+                    return Outcome::NoMatch;
+                }
+            }
+            b'?' => {
+                if t_ch == b'/' {
+                    return Outcome::NoMatch;
+                }
+            }
+            b'*' => {
+                p += 1;
+                let match_slash = if at(pattern, p) == b'*' {
+                    let prev = p.checked_sub(2);
+                    while at(pattern, p) == b'*' {
+                        p += 1;
+                    }
+                    let next = at(pattern, p);
+                    // "**" only matches across slashes as a whole path component
+                    if prev.is_none_or(|prev| pattern[prev] == b'/')
+                        && (next == 0
+                            || next == b'/'
+                            || (next == b'\\' && at(pattern, p + 1) == b'/'))
+                    {
+                        // "**/" can match no directories at all
+                        if next == b'/' && dowild(&pattern[p + 1..], &text[t..]) == Outcome::Match {
+                            return Outcome::Match;
+                        }
+                        true
+                    } else {
+                        false
+                    }
+                } else {
+                    false
+                };
+                let next = at(pattern, p);
+                if next == 0 {
+                    // A trailing "**" matches everything, a trailing "*" only the last component
+                    return if !match_slash && text[t..].contains(&b'/') {
+                        Outcome::NoMatch
+                    } else {
+                        Outcome::Match
+                    };
+                } else if !match_slash && next == b'/' {
+                    // A single "*" followed by "/" matches up to the next slash, which the
+                    // increments below then step over
+                    match text[t..].iter().position(|ch| *ch == b'/') {
+                        Some(offset) => t += offset,
+                        None => return Outcome::NoMatch,
+                    }
+                } else {
+                    while at(text, t) != 0 {
+                        let t_ch = text[t];
+                        match dowild(&pattern[p..], &text[t..]) {
+                            Outcome::NoMatch => {
+                                if !match_slash && t_ch == b'/' {
+                                    return Outcome::AbortToStarStar;
+                                }
+                            }
+                            Outcome::AbortToStarStar if match_slash => {}
+                            outcome => return outcome,
+                        }
+                        t += 1;
+                    }
+                    return Outcome::AbortAll;
+                }
+            }
+            b'[' => {
+                p += 1;
+                let mut p_ch = at(pattern, p);
+                let negated = p_ch == b'!' || p_ch == b'^';
+                if negated {
+                    p += 1;
+                    p_ch = at(pattern, p);
+                }
+                let mut prev_ch = 0;
+                let mut matched = false;
+                // The first character is always part of the set, even if it's "]"
+                loop {
+                    if p_ch == 0 {
+                        return Outcome::AbortAll;
+                    }
+                    if p_ch == b'\\' {
+                        p += 1;
+                        p_ch = at(pattern, p);
+                        if p_ch == 0 {
+                            return Outcome::AbortAll;
+                        }
+                        if t_ch == p_ch {
+                            matched = true;
+                        }
+                    } else if p_ch == b'-'
+                        && prev_ch != 0
+                        && at(pattern, p + 1) != 0
+                        && at(pattern, p + 1) != b']'
+                    {
+                        p += 1;
+                        p_ch = at(pattern, p);
+                        if p_ch == b'\\' {
+                            p += 1;
+                            p_ch = at(pattern, p);
+                            if p_ch == 0 {
+                                return Outcome::AbortAll;
+                            }
+                        }
+                        if t_ch <= p_ch && t_ch >= prev_ch {
+                            matched = true;
+                        }
+                        // A range can't be the start of another one
+                        p_ch = 0;
+                    } else if p_ch == b'[' && at(pattern, p + 1) == b':' {
+                        p += 2;
+                        let start = p;
+                        while at(pattern, p) != 0 && at(pattern, p) != b']' {
+                            p += 1;
+                        }
+                        if at(pattern, p) == 0 {
+                            return Outcome::AbortAll;
+                        }
+                        if p == start || pattern[p - 1] != b':' {
+                            // No ":]", so it's just a "[" in the set
+                            p = start - 2;
+                            p_ch = b'[';
+                            if t_ch == p_ch {
+                                matched = true;
+                            }
+                        } else {
+                            match in_class(&pattern[start..p - 1], t_ch) {
+                                Some(true) => matched = true,
+                                Some(false) => {}
+                                None => return Outcome::AbortAll,
+                            }
+                            p_ch = 0;
+                        }
+                    } else if t_ch == p_ch {
+                        matched = true;
+                    }
+                    prev_ch = p_ch;
+                    p += 1;
+                    p_ch = at(pattern, p);
+                    if p_ch == b']' {
+                        break;
+                    }
+                }
+                if matched == negated || t_ch == b'/' {
+                    return Outcome::NoMatch;
+                }
+            }
+            _ => {
+                if t_ch != p_ch {
+                    return Outcome::NoMatch;
+                }
+            }
+        }
+        p += 1;
+        t += 1;
+    }
+    if t < text.len() {
+        Outcome::NoMatch
+    } else {
+        Outcome::Match
+    }
+}
+
+// Whether a character is in a "[:class:]", or None if there's no such class.
+fn in_class(class: &[u8], ch: u8) -> Option<bool> {
+    let result = match class {
+        b"alnum" => ch.is_ascii_alphanumeric(),
+        b"alpha" => ch.is_ascii_alphabetic(),
+        b"blank" => ch == b' ' || ch == b'\t',
+        b"cntrl" => ch.is_ascii_control(),
+        b"digit" => ch.is_ascii_digit(),
+        b"graph" => ch.is_ascii_graphic(),
+        b"lower" => ch.is_ascii_lowercase(),
+        b"print" => ch.is_ascii_graphic() || ch == b' ',
+        b"punct" => ch.is_ascii_punctuation(),
+        b"space" => ch.is_ascii_whitespace() || ch == 0x0b,
+        b"upper" => ch.is_ascii_uppercase(),
+        b"xdigit" => ch.is_ascii_hexdigit(),
+        _ => return None,
+    };
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wildmatch;
+
+    // Text, pattern and whether they match, from the WM_PATHNAME column of git's t3070-wildmatch
+    const CASES: &[(&str, &str, bool)] = &[
+        // Basic wildmatch features
+        ("foo", "foo", true),
+        ("foo", "bar", false),
+        ("", "", true),
+        ("foo", "???", true),
+        ("foo", "??", false),
+        ("foo", "*", true),
+        ("foo", "f*", true),
+        ("foo", "*f", false),
+        ("foo", "*foo*", true),
+        ("foobar", "*ob*a*r*", true),
+        ("aaaaaaabababab", "*ab", true),
+        ("foo*", "foo\\*", true),
+        ("foobar", "foo\\*bar", false),
+        ("f\\oo", "f\\\\oo", true),
+        ("ball", "*[al]?", true),
+        ("ten", "[ten]", false),
+        ("ten", "**[!te]", true),
+        ("ten", "**[!ten]", false),
+        ("ten", "t[a-g]n", true),
+        ("ten", "t[!a-g]n", false),
+        ("ton", "t[!a-g]n", true),
+        ("ton", "t[^a-g]n", true),
+        ("a]b", "a[]]b", true),
+        ("a-b", "a[]-]b", true),
+        ("a]b", "a[]-]b", true),
+        ("aab", "a[]-]b", false),
+        ("aab", "a[]a-]b", true),
+        ("]", "]", true),
+        // Extended slash-matching features
+        ("foo/baz/bar", "foo*bar", false),
+        ("foo/baz/bar", "foo**bar", false),
+        ("foobazbar", "foo**bar", true),
+        ("foo/baz/bar", "foo/**/bar", true),
+        ("foo/baz/bar", "foo/**/**/bar", true),
+        ("foo/b/a/z/bar", "foo/**/bar", true),
+        ("foo/b/a/z/bar", "foo/**/**/bar", true),
+        ("foo/bar", "foo/**/bar", true),
+        ("foo/bar", "foo/**/**/bar", true),
+        ("foo/bar", "foo?bar", false),
+        ("foo/bar", "foo[/]bar", false),
+        ("foo/bar", "foo[^a-z]bar", false),
+        ("foo/bar", "f[^eiu][^eiu][^eiu][^eiu][^eiu]r", false),
+        ("foo-bar", "f[^eiu][^eiu][^eiu][^eiu][^eiu]r", true),
+        ("foo", "**/foo", true),
+        ("XXX/foo", "**/foo", true),
+        ("bar/baz/foo", "**/foo", true),
+        ("bar/baz/foo", "*/foo", false),
+        ("foo/bar/baz", "**/bar*", false),
+        ("deep/foo/bar/baz", "**/bar/*", true),
+        ("deep/foo/bar/baz/", "**/bar/*", false),
+        ("deep/foo/bar/baz/", "**/bar/**", true),
+        ("deep/foo/bar", "**/bar/*", false),
+        ("deep/foo/bar/", "**/bar/**", true),
+        ("foo/bar/baz", "**/bar**", false),
+        ("foo/bar/baz/x", "*/bar/**", true),
+        ("deep/foo/bar/baz/x", "*/bar/**", false),
+        ("deep/foo/bar/baz/x", "**/bar/*/*", true),
+        // Various additional tests
+        ("acrt", "a[c-c]st", false),
+        ("acrt", "a[c-c]rt", true),
+        ("]", "[!]-]", false),
+        ("a", "[!]-]", true),
+        ("", "\\", false),
+        ("\\", "\\", false),
+        ("XXX/\\", "*/\\", false),
+        ("XXX/\\", "*/\\\\", true),
+        ("foo", "foo", true),
+        ("@foo", "@foo", true),
+        ("foo", "@foo", false),
+        ("[ab]", "\\[ab]", true),
+        ("[ab]", "[[]ab]", true),
+        ("[ab]", "[[:]ab]", true),
+        ("[ab]", "[[::]ab]", false),
+        ("[ab]", "[[:digit]ab]", true),
+        ("[ab]", "[\\[:]ab]", true),
+        ("?a?b", "\\??\\?b", true),
+        ("abc", "\\a\\b\\c", true),
+        ("foo", "", false),
+        ("foo/bar/baz/to", "**/t[o]", true),
+        // Character class tests
+        ("a1B", "[[:alpha:]][[:digit:]][[:upper:]]", true),
+        ("a", "[[:digit:][:upper:][:space:]]", false),
+        ("A", "[[:digit:][:upper:][:space:]]", true),
+        ("1", "[[:digit:][:upper:][:space:]]", true),
+        ("1", "[[:digit:][:upper:][:spaci:]]", false),
+        (" ", "[[:digit:][:upper:][:space:]]", true),
+        (".", "[[:digit:][:upper:][:space:]]", false),
+        (".", "[[:digit:][:punct:][:space:]]", true),
+        ("5", "[[:xdigit:]]", true),
+        ("f", "[[:xdigit:]]", true),
+        ("D", "[[:xdigit:]]", true),
+        (
+            "_",
+            "[[:alnum:][:alpha:][:blank:][:cntrl:][:digit:][:graph:][:lower:][:print:][:punct:][:space:][:upper:][:xdigit:]]",
+            true,
+        ),
+        (".", "[^[:alnum:][:alpha:][:blank:][:cntrl:][:digit:][:lower:][:space:][:upper:][:xdigit:]]", true),
+        ("5", "[a-c[:digit:]x-z]", true),
+        ("b", "[a-c[:digit:]x-z]", true),
+        ("y", "[a-c[:digit:]x-z]", true),
+        ("q", "[a-c[:digit:]x-z]", false),
+        // Additional tests, including some malformed wildmatch patterns
+        ("]", "[\\\\-^]", true),
+        ("[", "[\\\\-^]", false),
+        ("-", "[\\-_]", true),
+        ("]", "[\\]]", true),
+        ("\\]", "[\\]]", false),
+        ("\\", "[\\]]", false),
+        ("ab", "a[]b", false),
+        ("a[]b", "a[]b", false),
+        ("ab[", "ab[", false),
+        ("ab", "[!", false),
+        ("ab", "[-", false),
+        ("-", "[-]", true),
+        ("-", "[a-", false),
+        ("-", "[!a-", false),
+        ("-", "[--A]", true),
+        ("5", "[--A]", true),
+        (" ", "[ --]", true),
+        ("$", "[ --]", true),
+        ("-", "[ --]", true),
+        ("0", "[ --]", false),
+        ("-", "[---]", true),
+        ("-", "[------]", true),
+        ("j", "[a-e-n]", false),
+        ("-", "[a-e-n]", true),
+        ("a", "[!------]", true),
+        ("[", "[]-a]", false),
+        ("^", "[]-a]", true),
+        ("^", "[!]-a]", false),
+        ("[", "[!]-a]", true),
+        ("^", "[a^bc]", true),
+        ("-b]", "[a-]b]", true),
+        ("\\", "[\\]", false),
+        ("\\", "[\\\\]", true),
+        ("\\", "[!\\\\]", false),
+        ("G", "[A-\\\\]", true),
+        ("aaabbb", "b*a", false),
+        ("aabcaa", "*ba*", false),
+        (",", "[,]", true),
+        (",", "[\\\\,]", true),
+        ("\\", "[\\\\,]", true),
+        ("-", "[,-.]", true),
+        ("+", "[,-.]", false),
+        ("-.]", "[,-.]", false),
+        ("2", "[\\1-\\3]", true),
+        ("3", "[\\1-\\3]", true),
+        ("4", "[\\1-\\3]", false),
+        ("\\", "[[-\\]]", true),
+        ("[", "[[-\\]]", true),
+        ("]", "[[-\\]]", true),
+        ("-", "[[-\\]]", false),
+        // Test recursion
+        (
+            "-adobe-courier-bold-o-normal--12-120-75-75-m-70-iso8859-1",
+            "-*-*-*-*-*-*-12-*-*-*-m-*-*-*",
+            true,
+        ),
+        (
+            "-adobe-courier-bold-o-normal--12-120-75-75-X-70-iso8859-1",
+            "-*-*-*-*-*-*-12-*-*-*-m-*-*-*",
+            false,
+        ),
+        (
+            "-adobe-courier-bold-o-normal--12-120-75-75-/-70-iso8859-1",
+            "-*-*-*-*-*-*-12-*-*-*-m-*-*-*",
+            false,
+        ),
+        ("XXX/adobe/courier/bold/o/normal//12/120/75/75/m/70/iso8859/1", "XXX/*/*/*/*/*/*/12/*/*/*/m/*/*/*", true),
+        ("XXX/adobe/courier/bold/o/normal//12/120/75/75/X/70/iso8859/1", "XXX/*/*/*/*/*/*/12/*/*/*/m/*/*/*", false),
+        ("abcd/abcdefg/abcdefghijk/abcdefghijklmnop.txt", "**/*a*b*g*n*t", true),
+        ("abcd/abcdefg/abcdefghijk/abcdefghijklmnop.txtz", "**/*a*b*g*n*t", false),
+        ("foo", "*/*/*", false),
+        ("foo/bar", "*/*/*", false),
+        ("foo/bba/arr", "*/*/*", true),
+        ("foo/bb/aa/rr", "*/*/*", false),
+        ("foo/bb/aa/rr", "**/**/**", true),
+        ("abcXdefXghi", "*X*i", true),
+        ("ab/cXd/efXg/hi", "*X*i", false),
+        ("ab/cXd/efXg/hi", "*/*X*/*/*i", true),
+        ("ab/cXd/efXg/hi", "**/*X*/**/*i", true),
+    ];
+
+    #[test]
+    fn matches_like_git() {
+        for (text, pattern, expected) in CASES {
+            assert_eq!(
+                wildmatch(pattern, text),
+                *expected,
+                "{:?} against {:?}",
+                pattern,
+                text
+            );
+        }
+    }
+}
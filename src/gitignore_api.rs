@@ -3,11 +3,32 @@
  * Created 2024-04-10
  */
 
+use std::collections::HashMap;
+
+use futures::StreamExt;
+
+use crate::gitignore::generated::{assemble, assemble_toptal, template_body, Assembled, Layout};
 use crate::gitignore_api::error::Error;
 
 mod error;
 
 const API_URL: &str = "https://www.toptal.com/developers/gitignore/api";
+const EDIT_URL: &str = "https://www.toptal.com/developers/gitignore";
+// How many templates to fetch at once
+const FETCH_LIMIT: usize = 8;
+
+// A template as the API lists it with "?format=json"
+#[derive(serde::Deserialize)]
+struct ListedTemplate {
+    contents: String,
+}
+
+/// Rules for several templates, fetched one by one and put together
+pub(crate) struct Fetched {
+    pub(crate) assembled: Assembled,
+    /// Templates that couldn't be fetched, and were left out
+    pub(crate) failed: Vec<(String, Error)>,
+}
 
 pub(super) async fn get_template_names() -> Result<Vec<String>, Error> {
     let url = format!("{API_URL}/list");
@@ -25,11 +46,86 @@ pub(super) async fn get_template_names() -> Result<Vec<String>, Error> {
     Ok(vec)
 }
 
-pub(super) async fn get_template(template_names: &[String]) -> Result<String, Error> {
-    let url = format!("{API_URL}/{}", template_names.join(","));
+// Every template's rules, by name, sorted by name
+pub(super) async fn get_template_bodies() -> Result<Vec<(String, String)>, Error> {
+    let url = format!("{API_URL}/list?format=json");
+    let response = get(&reqwest::Client::new(), &url).await?;
+    let listed: HashMap<String, ListedTemplate> = serde_json::from_str(&response)?;
+    let mut bodies: Vec<(String, String)> = listed
+        .into_iter()
+        .map(|(name, template)| (name, template.contents))
+        .collect();
+    bodies.sort();
+    Ok(bodies)
+}
+
+// Where the rules for one or more templates come from
+pub(super) fn template_url(template_names: &[String]) -> String {
+    format!("{API_URL}/{}", template_names.join(","))
+}
+
+// Where to choose templates on the website, starting with these
+pub(super) fn edit_url(template_names: &[String]) -> String {
+    format!("{EDIT_URL}?templates={}", template_names.join(","))
+}
+
+// Fetches each template on its own, several at a time, returning the responses in the order the
+// names were given. One that can't be fetched doesn't stop the others.
+pub(super) async fn get_templates(
+    template_names: &[String],
+) -> Vec<(String, Result<String, Error>)> {
     let client = reqwest::Client::new();
-    let request_builder = client.get(url);
-    let response = request_builder.send().await?;
+    futures::stream::iter(template_names)
+        .map(|name| {
+            let client = &client;
+            async move {
+                let url = template_url(std::slice::from_ref(name));
+                (name.clone(), get(client, &url).await)
+            }
+        })
+        .buffered(FETCH_LIMIT)
+        .collect()
+        .await
+}
+
+// Fetches the templates and puts them together. It only fails if none of them can be fetched.
+pub(super) async fn get_gitignore(
+    template_names: &[String],
+    layout: Layout,
+) -> Result<Fetched, Error> {
+    // The API ignores repeats, whatever their case
+    let mut names: Vec<String> = vec![];
+    for name in template_names {
+        if !names.iter().any(|other| other.eq_ignore_ascii_case(name)) {
+            names.push(name.clone());
+        }
+    }
+    let mut responses = vec![];
+    let mut failed = vec![];
+    for (name, response) in get_templates(&names).await {
+        match response {
+            Ok(response) => responses.push((name, response)),
+            Err(error) => failed.push((name, error)),
+        }
+    }
+    if responses.is_empty() && !failed.is_empty() {
+        return Err(failed.swap_remove(0).1);
+    }
+    let assembled = match layout {
+        Layout::Given => {
+            let bodies: Vec<(String, String)> = responses
+                .into_iter()
+                .map(|(name, response)| (name, template_body(&response)))
+                .collect();
+            assemble(&bodies)
+        }
+        Layout::Toptal => assemble_toptal(&responses),
+    };
+    Ok(Fetched { assembled, failed })
+}
+
+async fn get(client: &reqwest::Client, url: &str) -> Result<String, Error> {
+    let response = client.get(url).send().await?;
     let response = response.error_for_status()?;
     Ok(response.text().await?)
 }
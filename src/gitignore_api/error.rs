@@ -6,12 +6,14 @@
 #[derive(Debug)]
 pub(crate) enum Error {
     Reqwest(reqwest::Error),
+    Json(serde_json::Error),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let (source, message) = match self {
             Error::Reqwest(error) => ("reqwest", error.to_string()),
+            Error::Json(error) => ("serde_json", error.to_string()),
         };
         write!(f, "[{}] {}", source, message)
     }
@@ -23,4 +25,10 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Error {
+        Error::Json(error)
+    }
+}
+
 impl std::error::Error for Error {}
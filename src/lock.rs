@@ -0,0 +1,114 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-27
+ */
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::{util, writer};
+
+pub(crate) const LOCK_FILE_NAME: &str = ".gi-gen.lock";
+
+/// The templates a .gitignore was generated from, pinned to the content they had then
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Lock {
+    pub(crate) templates: Vec<LockedTemplate>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct LockedTemplate {
+    pub(crate) name: String,
+    /// The URL the template was fetched from
+    pub(crate) source: String,
+    /// The SHA-256 hash of the template's rules, in hex
+    pub(crate) sha256: String,
+}
+
+impl Lock {
+    /// Reads a lock file, or returns None if there isn't one.
+    pub(crate) fn read(path: &Path) -> std::io::Result<Option<Self>> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|error| std::io::Error::new(ErrorKind::InvalidData, error))
+    }
+    pub(crate) fn write(&self, path: &Path) -> std::io::Result<()> {
+        let text = serde_json::to_string_pretty(self)?;
+        writer::save(path, &format!("{}\n", text), false)
+    }
+    pub(crate) fn find(&self, name: &str) -> Option<&LockedTemplate> {
+        self.templates
+            .iter()
+            .find(|template| template.name.eq_ignore_ascii_case(name))
+    }
+}
+
+pub(crate) fn sha256(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Where template content is saved by its hash
+pub(crate) fn cache_dir() -> Option<PathBuf> {
+    util::data_dir().map(|dir| dir.join("templates"))
+}
+
+/// Template content saved by its hash in `dir`, if it's there and still matches it
+pub(crate) fn cached(dir: &Path, hash: &str) -> Option<String> {
+    let content = std::fs::read_to_string(dir.join(hash)).ok()?;
+    (sha256(&content) == hash).then_some(content)
+}
+
+/// Saves template content by its hash in `dir`.
+pub(crate) fn cache(dir: &Path, content: &str) -> std::io::Result<()> {
+    writer::save(&dir.join(sha256(content)), content, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cache, cached, sha256, Lock, LockedTemplate};
+    use crate::testing::TempDir;
+
+    #[test]
+    fn checks_cached_content_by_its_hash() {
+        let dir = TempDir::new();
+        let content = "### Rust ###\n/target\n";
+        let hash = sha256(content);
+        assert_eq!(cached(dir.path(), &hash), None);
+        cache(dir.path(), content).unwrap();
+        assert_eq!(cached(dir.path(), &hash).as_deref(), Some(content));
+        // Content that no longer matches its hash isn't used
+        std::fs::write(dir.path().join(&hash), "### Rust ###\n").unwrap();
+        assert_eq!(cached(dir.path(), &hash), None);
+    }
+
+    #[test]
+    fn reads_what_it_wrote() {
+        let dir = TempDir::new();
+        let path = dir.path().join(".gi-gen.lock");
+        assert_eq!(Lock::read(&path).unwrap(), None);
+        let lock = Lock {
+            templates: vec![LockedTemplate {
+                name: "rust".to_string(),
+                source: "https://www.toptal.com/developers/gitignore/api/rust".to_string(),
+                sha256: sha256("/target\n"),
+            }],
+        };
+        lock.write(&path).unwrap();
+        let read = Lock::read(&path).unwrap().unwrap();
+        assert_eq!(read, lock);
+        assert!(read.find("Rust").is_some());
+        assert!(read.find("node").is_none());
+        std::fs::write(&path, "{").unwrap();
+        assert!(Lock::read(&path).is_err());
+    }
+}
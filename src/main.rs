@@ -3,33 +3,81 @@
  * Created 2024-04-11
  */
 
-use args::{Args, Commands, FilterArgs};
+use std::path::{Path, PathBuf};
+
+use args::{
+    Args, AuditArgs, CheckArgs, Commands, ExplainArgs, FilterArgs, Format, GenerateArgs,
+    InteractiveArgs, LintArgs, RuleArgs, RulesSourceArgs, SearchArgs, SuggestArgs, UndoArgs,
+};
+use gitignore::annotate::{self, Annotate};
+use gitignore::generated::{assemble, find_blocks, template_body, Assembled, Layout};
+use gitignore::matcher::Matcher;
+use gitignore::minimize::{self, Dropped};
+use gitignore::Gitignore;
+use gitignore::{conflicts, dedupe, export, lint};
+use lock::{Lock, LockedTemplate};
+use suggest::Suggestions;
 use tui::event::Event;
+use writer::target::{self, Target};
+use writer::LineEnding;
 
 use crate::app::App;
 
 mod app;
 mod args;
+mod audit;
+mod diff;
+mod git;
+mod gitignore;
 mod gitignore_api;
+mod lock;
+mod preview;
+mod search;
+mod suggest;
+#[cfg(test)]
+mod testing;
 mod tui;
 mod util;
+mod writer;
 
 #[tokio::main]
 async fn main() -> color_eyre::eyre::Result<()> {
     let args: Args = clap::Parser::parse();
-    let command = args.command.unwrap_or(Commands::Interactive);
+    let command = args
+        .command
+        .unwrap_or(Commands::Interactive(InteractiveArgs::default()));
     match command {
         Commands::List(args) => print_templates(args).await,
-        Commands::Generate(args) => print_gitignore(args.templates).await,
-        Commands::Interactive => run_tui().await,
+        Commands::Generate(args) => print_gitignore(args).await,
+        Commands::Interactive(args) => run_tui(args, None).await,
+        Commands::Undo(args) => undo(args),
+        Commands::Explain(args) => explain(args).await,
+        Commands::Audit(args) => audit(args).await,
+        Commands::Lint(args) => lint(args),
+        Commands::Check(args) => check(args).await,
+        Commands::Suggest(args) => suggest(args).await,
+        Commands::Search(args) => search(args).await,
     }
 }
 
-async fn run_tui() -> color_eyre::eyre::Result<()> {
+async fn run_tui(
+    args: InteractiveArgs,
+    suggestions: Option<Suggestions>,
+) -> color_eyre::eyre::Result<()> {
     let mut tui = tui::Tui::new().unwrap().mouse(true);
     tui.enter()?;
     let mut app = App::default();
-    app.set_event_tx(Some(tui.event_tx.clone()));
+    app.set_event_tx(Some(tui.event_tx.clone()))
+        .set_output(args.output)
+        .set_here(args.target_args.here)
+        .set_no_backup(args.target_args.no_backup)
+        .set_dedupe(args.rule_args.dedupe)
+        .set_minimize(args.rule_args.minimize)
+        .set_annotate(args.annotate)
+        .set_layout(args.rule_args.layout);
+    if let Some(suggestions) = suggestions {
+        app.set_suggestions(suggestions);
+    }
 
     loop {
         let event = tui.next().await?; // blocks until next event
@@ -72,18 +120,815 @@ async fn print_templates(args: FilterArgs) -> color_eyre::eyre::Result<()> {
     Ok(())
 }
 
-async fn print_gitignore(template_names: Vec<String>) -> color_eyre::eyre::Result<()> {
-    match gitignore_api::get_template(&template_names).await {
-        Ok(result) => {
-            println!("{}", result);
-            Ok(())
+async fn print_gitignore(args: GenerateArgs) -> color_eyre::eyre::Result<()> {
+    let template_names = args.template_args.templates;
+    let output = args.output.clone().flatten();
+    let target = match args.target {
+        Some(kind) => {
+            target::resolve_named(output.as_deref(), args.target_args.here, kind.file_name())
+        }
+        None => target::resolve(output.as_deref(), args.target_args.here),
+    };
+    let assembled = if args.lock {
+        let lock_path = target.path.with_file_name(lock::LOCK_FILE_NAME);
+        generate_locked(&template_names, &lock_path).await?
+    } else {
+        fetch_gitignore(&template_names, args.rule_args.layout, args.allow_missing).await?
+    };
+    let (result, mut templates) = (assembled.content, assembled.templates);
+    for conflict in conflicts::conflicts(&result, &templates) {
+        eprintln!("[{}] Warning: {}", env!("CARGO_PKG_NAME"), conflict.message);
+    }
+    let (mut result, dropped) = process_rules(result, &args.rule_args);
+    for dropped in &dropped {
+        eprintln!(
+            r#"[{}] Dropped line {} "{}": {}"#,
+            env!("CARGO_PKG_NAME"),
+            dropped.line,
+            dropped.text,
+            dropped.reason
+        );
+    }
+    minimize::remap(&mut templates, &dropped);
+    if args.annotate == Some(Annotate::Comments) {
+        result = annotate::with_comments(&result, &templates);
+    }
+    // Exporting keeps the lines in order, though it may add some at the top
+    if let Some(kind) = args.target {
+        let exported = export::export(&result, kind);
+        result = exported.content;
+        for (_, range) in &mut templates {
+            *range = range.start + exported.added_lines..range.end + exported.added_lines;
+        }
+        for warning in exported.warnings {
+            match warning.line {
+                Some(line) => eprintln!(
+                    "[{}] Warning: {} line {}: {}",
+                    env!("CARGO_PKG_NAME"),
+                    kind.file_name(),
+                    line,
+                    warning.message
+                ),
+                None => eprintln!("[{}] Warning: {}", env!("CARGO_PKG_NAME"), warning.message),
+            }
+        }
+    }
+    if args.output.is_some() || args.target_args.here {
+        save_gitignore(&result, &target, args.eol, args.target_args.no_backup)?;
+        if args.annotate == Some(Annotate::Json) {
+            let sidecar_path = annotate::sidecar_path(&target.path);
+            writer::save(
+                &sidecar_path,
+                &annotate::sidecar(&result, &templates),
+                false,
+            )?;
+            eprintln!(
+                "[{}] Wrote {}.",
+                env!("CARGO_PKG_NAME"),
+                sidecar_path.display()
+            );
+        }
+        // Tracked files only matter to git
+        if args.target.is_none() {
+            if let Some(warning) = audit::warning(&target.path, &result) {
+                eprintln!("[{}] Warning: {}", env!("CARGO_PKG_NAME"), warning);
+            }
         }
+        Ok(())
+    } else {
+        // The JSON goes next to a saved file, and there isn't one
+        if args.annotate == Some(Annotate::Json) {
+            eprintln!(
+                "[{}] Warning: --annotate=json only writes its file when saving with --output or --here.",
+                env!("CARGO_PKG_NAME")
+            );
+        }
+        let result = match args.eol {
+            Some(line_ending) => line_ending.normalize(&result),
+            None => format!("{}\n", result),
+        };
+        print!("{}", result);
+        Ok(())
+    }
+}
+
+// Generates from the template content pinned in the lock file, taking it from the cache or, if
+// it's not there, from upstream as long as it hasn't changed. Templates that aren't pinned yet are
+// fetched and added to the lock file.
+async fn generate_locked(
+    template_names: &[String],
+    lock_path: &Path,
+) -> color_eyre::eyre::Result<Assembled> {
+    let cache_dir = lock::cache_dir().ok_or_else(|| {
+        color_eyre::eyre::eyre!("Couldn't find a directory to cache templates in")
+    })?;
+    generate_locked_in(template_names, lock_path, &cache_dir, |names| async move {
+        gitignore_api::get_templates(&names).await
+    })
+    .await
+}
+
+// Like `generate_locked`, with where template content is cached and how it's fetched given
+async fn generate_locked_in<F, R, E>(
+    template_names: &[String],
+    lock_path: &Path,
+    cache_dir: &Path,
+    fetch: F,
+) -> color_eyre::eyre::Result<Assembled>
+where
+    F: FnOnce(Vec<String>) -> R,
+    R: std::future::Future<Output = Vec<(String, Result<String, E>)>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let lock = Lock::read(lock_path)?;
+    let template_names: Vec<String> = if template_names.is_empty() {
+        lock.iter()
+            .flat_map(|lock| lock.templates.iter())
+            .map(|template| template.name.clone())
+            .collect()
+    } else {
+        template_names.to_vec()
+    };
+    if template_names.is_empty() {
+        return Err(color_eyre::eyre::eyre!(
+            "No templates were given, and {} doesn't list any",
+            lock_path.display()
+        ));
+    }
+    let pinned = |name: &str| lock.as_ref().and_then(|lock| lock.find(name));
+    let mut cached: Vec<Option<String>> = template_names
+        .iter()
+        .map(|name| pinned(name).and_then(|pinned| lock::cached(cache_dir, &pinned.sha256)))
+        .collect();
+    let uncached: Vec<String> = template_names
+        .iter()
+        .zip(&cached)
+        .filter(|(_, body)| body.is_none())
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut fetched = fetch(uncached).await.into_iter();
+    let mut templates = vec![];
+    let mut locked = Lock::default();
+    for (name, body) in template_names.into_iter().zip(cached.iter_mut()) {
+        let pinned = pinned(&name);
+        let body = match body.take() {
+            Some(body) => body,
+            None => {
+                let body = match fetched.next() {
+                    Some((_, Ok(response))) => template_body(&response),
+                    Some((_, Err(error))) => return Err(fetch_error(&[name], error)),
+                    None => unreachable!("every uncached template is fetched"),
+                };
+                let hash = lock::sha256(&body);
+                if let Some(pinned) = pinned.filter(|pinned| pinned.sha256 != hash) {
+                    return Err(color_eyre::eyre::eyre!(
+                        "The {} template has changed since it was locked in {} (sha256 {}, now {}), and the locked content isn't cached. Remove it from the lock file to lock the current content.",
+                        name,
+                        lock_path.display(),
+                        pinned.sha256,
+                        hash
+                    ));
+                }
+                lock::cache(cache_dir, &body)?;
+                body
+            }
+        };
+        locked.templates.push(match pinned {
+            Some(pinned) => pinned.clone(),
+            None => LockedTemplate {
+                source: gitignore_api::template_url(std::slice::from_ref(&name)),
+                sha256: lock::sha256(&body),
+                name: name.clone(),
+            },
+        });
+        templates.push((name, body));
+    }
+    if lock.as_ref() != Some(&locked) {
+        locked.write(lock_path)?;
+        eprintln!(
+            "[{}] Wrote {}.",
+            env!("CARGO_PKG_NAME"),
+            lock_path.display()
+        );
+    }
+    Ok(assemble(&templates))
+}
+
+// Fetches the templates and puts them together. If any can't be fetched, that's an error unless
+// `allow_missing` is set, when they're left out with a warning.
+async fn fetch_gitignore(
+    template_names: &[String],
+    layout: Layout,
+    allow_missing: bool,
+) -> color_eyre::eyre::Result<Assembled> {
+    if layout.reorders(template_names) {
+        eprintln!(
+            "[{}] Warning: --layout toptal puts the templates in order by name, not in the order given.",
+            env!("CARGO_PKG_NAME")
+        );
+    }
+    match gitignore_api::get_gitignore(template_names, layout).await {
+        Ok(mut fetched) if !allow_missing && !fetched.failed.is_empty() => {
+            eprintln!(
+                "[{}] Use --allow-missing to leave out templates that can't be fetched.",
+                env!("CARGO_PKG_NAME")
+            );
+            let names: Vec<String> = fetched
+                .failed
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect();
+            let (_, error) = fetched.failed.swap_remove(0);
+            Err(fetch_error(&names, error))
+        }
+        Ok(fetched) => {
+            for (name, error) in &fetched.failed {
+                eprintln!(
+                    "[{}] Left out the {} template, which couldn't be fetched: {}",
+                    env!("CARGO_PKG_NAME"),
+                    name,
+                    error
+                );
+            }
+            Ok(fetched.assembled)
+        }
+        Err(error) => Err(fetch_error(template_names, error)),
+    }
+}
+
+// Reports which templates couldn't be fetched.
+fn fetch_error(
+    template_names: &[String],
+    error: impl std::error::Error + Send + Sync + 'static,
+) -> color_eyre::eyre::Report {
+    eprint!(
+        r#"Problem getting .gitignore for "{}": "#,
+        template_names.join(" ")
+    );
+    color_eyre::eyre::Report::new(error)
+}
+
+// Applies the options that change the generated rules, returning any rules that were dropped.
+fn process_rules(content: String, rule_args: &RuleArgs) -> (String, Vec<Dropped>) {
+    let (content, dropped) = if rule_args.minimize {
+        minimize::minimize(&content)
+    } else {
+        (content, vec![])
+    };
+    if rule_args.dedupe {
+        (dedupe::dedupe(&content), dropped)
+    } else {
+        (content, dropped)
+    }
+}
+
+// Replaces the target file, keeping its line endings unless told otherwise.
+fn save_gitignore(
+    content: &str,
+    target: &Target,
+    eol: Option<LineEnding>,
+    no_backup: bool,
+) -> color_eyre::eyre::Result<()> {
+    if let Some(warning) = &target.warning {
+        eprintln!("[{}] Warning: {}", env!("CARGO_PKG_NAME"), warning);
+    }
+    let existing = std::fs::read_to_string(&target.path).ok();
+    let line_ending = eol
+        .or_else(|| existing.as_deref().and_then(LineEnding::detect))
+        .unwrap_or_default();
+    writer::save(&target.path, &line_ending.normalize(content), !no_backup)?;
+    let verb = if existing.is_some() {
+        "Replaced contents of"
+    } else {
+        "Created"
+    };
+    println!(
+        "[{}] {} {}.",
+        env!("CARGO_PKG_NAME"),
+        verb,
+        target.path.display()
+    );
+    Ok(())
+}
+
+fn undo(args: UndoArgs) -> color_eyre::eyre::Result<()> {
+    let file = target::resolve(args.file.as_deref(), args.here).path;
+    let backup = writer::backup::restore(&file)?;
+    if backup.created() {
+        println!(
+            "[{}] Removed {}, which didn't exist before it was saved at {}.",
+            env!("CARGO_PKG_NAME"),
+            file.display(),
+            backup.timestamp()
+        );
+    } else {
+        println!(
+            "[{}] Restored {} from the backup taken at {}.",
+            env!("CARGO_PKG_NAME"),
+            file.display(),
+            backup.timestamp()
+        );
+    }
+    Ok(())
+}
+
+async fn explain(args: ExplainArgs) -> color_eyre::eyre::Result<()> {
+    let (file, content, source) = read_rules(&args.source_args).await?;
+    let gitignore = Gitignore::parse(&content);
+    let matcher = Matcher::new(&gitignore);
+    let rules_dir = git::rules_dir(&file);
+    for path in &args.paths {
+        let absolute = util::absolute_path(path);
+        let relative = match util::relative_path(&rules_dir, &absolute) {
+            Some(relative) if !relative.is_empty() => relative,
+            _ => {
+                println!(
+                    "{}: not checked, it's not under {}",
+                    path.display(),
+                    rules_dir.display()
+                );
+                continue;
+            }
+        };
+        let is_dir = path.to_string_lossy().ends_with(['/', '\\']) || absolute.is_dir();
+        let found = match matcher.check(&relative, is_dir) {
+            Some(found) => found,
+            None => {
+                println!("{}: not ignored, no rule matches it", path.display());
+                continue;
+            }
+        };
+        let rule = describe_rule(&gitignore, &source, found.line.number);
+        if !found.ignored() {
+            println!("{}: not ignored, re-included by {}", path.display(), rule);
+        } else if found.path != relative {
+            println!(
+                "{}: ignored by {}, which matches the directory {}/",
+                path.display(),
+                rule,
+                found.path
+            );
+        } else {
+            println!("{}: ignored by {}", path.display(), rule);
+        }
+    }
+    Ok(())
+}
+
+async fn audit(args: AuditArgs) -> color_eyre::eyre::Result<()> {
+    let (file, content, source) = read_rules(&args.source_args).await?;
+    let rules_dir = git::rules_dir(&file);
+    let Some(repository) = git::Repository::discover(&rules_dir) else {
+        return Err(color_eyre::eyre::eyre!(
+            "{} isn't in a git repository",
+            rules_dir.display()
+        ));
+    };
+    let findings = audit::audit(&repository, &rules_dir, &content)?;
+    if findings.is_empty() {
+        println!(
+            "[{}] No tracked files match the rules.",
+            env!("CARGO_PKG_NAME")
+        );
+        return Ok(());
+    }
+    let gitignore = Gitignore::parse(&content);
+    let paths: Vec<String> = findings
+        .iter()
+        .map(|finding| finding.path.clone())
+        .collect();
+    if args.run {
+        repository.untrack(&paths)?;
+        println!(
+            "[{}] Stopped tracking {} file(s); commit to make it stick.",
+            env!("CARGO_PKG_NAME"),
+            paths.len()
+        );
+    } else if args.fix {
+        // ":/" makes the path relative to the top of the working tree, wherever this is run
+        for path in &paths {
+            println!(
+                "git rm --cached -- {}",
+                util::shell_quote(&format!(":/{}", path))
+            );
+        }
+    } else {
+        for finding in &findings {
+            println!(
+                "{}: tracked, but matches {}",
+                finding.path,
+                describe_rule(&gitignore, &source, finding.line)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn lint(args: LintArgs) -> color_eyre::eyre::Result<()> {
+    let file = target::resolve(args.file.as_deref(), args.here).path;
+    let content = match std::fs::read_to_string(&file) {
+        Ok(content) => content,
         Err(error) => {
-            eprint!(
-                r#"Problem getting .gitignore for "{}": "#,
-                template_names.join(" ")
+            eprint!("Problem reading {}: ", file.display());
+            return Err(color_eyre::eyre::Report::new(error));
+        }
+    };
+    let diagnostics = lint::lint(&content);
+    let file = file.display().to_string();
+    match args.format {
+        Format::Text => {
+            for diagnostic in &diagnostics {
+                println!(
+                    "{}:{}: {}: {}",
+                    file, diagnostic.line, diagnostic.code, diagnostic.message
+                );
+            }
+        }
+        Format::Json => {
+            let diagnostics: Vec<serde_json::Value> = diagnostics
+                .iter()
+                .map(|diagnostic| {
+                    serde_json::json!({
+                        "file": file,
+                        "line": diagnostic.line,
+                        "code": diagnostic.code,
+                        "message": diagnostic.message,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+        }
+    }
+    if !diagnostics.is_empty() {
+        // So it can fail a CI job
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn check(args: CheckArgs) -> color_eyre::eyre::Result<()> {
+    let file = target::resolve(args.file.as_deref(), args.target_args.here).path;
+    let content = match std::fs::read_to_string(&file) {
+        Ok(content) => content,
+        Err(error) => {
+            eprint!("Problem reading {}: ", file.display());
+            return Err(color_eyre::eyre::Report::new(error));
+        }
+    };
+    let gitignore = Gitignore::parse(&content);
+    let blocks = find_blocks(&gitignore);
+    if blocks.is_empty() {
+        return Err(color_eyre::eyre::eyre!(
+            "{} has no rules generated from gitignore.io templates",
+            file.display()
+        ));
+    }
+    let lines = gitignore.lines();
+    let line_ending = LineEnding::detect(&content).unwrap_or_default();
+    let mut updated = String::new();
+    let mut next_line = 0;
+    let mut drifted = 0;
+    for block in &blocks {
+        // Rules left out for a template that couldn't be fetched would look removed upstream
+        let assembled =
+            match gitignore_api::get_gitignore(&block.templates, args.rule_args.layout).await {
+                Ok(fetched) if fetched.failed.is_empty() => fetched.assembled,
+                Ok(mut fetched) => {
+                    let (name, error) = fetched.failed.swap_remove(0);
+                    return Err(fetch_error(&[name], error));
+                }
+                Err(error) => return Err(fetch_error(&block.templates, error)),
+            };
+        let (mut current, dropped) = process_rules(assembled.content, &args.rule_args);
+        // The comments saying where each template's rules came from go back in, if it had them
+        let annotated = lines[block.lines.clone()]
+            .iter()
+            .any(|line| annotate::is_comment(&line.text));
+        if annotated {
+            let mut templates = assembled.templates;
+            minimize::remap(&mut templates, &dropped);
+            current = annotate::with_comments(&current, &templates);
+        }
+        let current_gitignore = Gitignore::parse(&current);
+        let block_rules: Vec<&str> = lines[block.lines.clone()]
+            .iter()
+            .filter(|line| line.rule().is_some())
+            .map(|line| line.text.trim())
+            .collect();
+        let current_rules: Vec<&str> = current_gitignore
+            .rules()
+            .map(|(line, _)| line.text.trim())
+            .collect();
+        let added: Vec<&&str> = current_rules
+            .iter()
+            .filter(|rule| !block_rules.contains(rule))
+            .collect();
+        let removed: Vec<&&str> = block_rules
+            .iter()
+            .filter(|rule| !current_rules.contains(rule))
+            .collect();
+        let location = format!(
+            "{}:{}-{} ({})",
+            file.display(),
+            block.lines.start + 1,
+            block.lines.end,
+            block.templates.join(", ")
+        );
+        if added.is_empty() && removed.is_empty() {
+            println!("{}: up to date", location);
+        } else {
+            drifted += 1;
+            println!(
+                "{}: {} rule(s) added and {} removed upstream",
+                location,
+                added.len(),
+                removed.len()
+            );
+            for rule in added {
+                println!("  + {}", rule);
+            }
+            for rule in removed {
+                println!("  - {}", rule);
+            }
+        }
+        // Everything up to the block stays as it is; the block itself is replaced
+        for line in &lines[next_line..block.lines.start] {
+            updated.push_str(&line.text);
+            updated.push_str(line.ending);
+        }
+        updated.push_str(&line_ending.normalize(current.trim_start_matches(['\r', '\n'])));
+        next_line = block.lines.end;
+    }
+    if drifted == 0 {
+        return Ok(());
+    }
+    if !args.update {
+        // So it can fail a CI job
+        std::process::exit(1);
+    }
+    let trailing = &lines[next_line..];
+    if trailing.is_empty() && !content.ends_with(['\r', '\n']) {
+        // Don't add a line ending the file didn't have
+        updated.truncate(updated.trim_end_matches(['\r', '\n']).len());
+    }
+    for line in trailing {
+        updated.push_str(&line.text);
+        updated.push_str(line.ending);
+    }
+    writer::save(&file, &updated, !args.target_args.no_backup)?;
+    println!(
+        "[{}] Updated {} block(s) of generated rules in {}.",
+        env!("CARGO_PKG_NAME"),
+        drifted,
+        file.display()
+    );
+    Ok(())
+}
+
+async fn suggest(args: SuggestArgs) -> color_eyre::eyre::Result<()> {
+    let interactive_args = args.interactive_args;
+    let file = target::resolve(
+        interactive_args.output.as_deref(),
+        interactive_args.target_args.here,
+    )
+    .path;
+    let rules_dir = git::rules_dir(&file);
+    let Some(repository) = git::Repository::discover(&rules_dir) else {
+        return Err(color_eyre::eyre::eyre!(
+            "{} isn't in a git repository",
+            rules_dir.display()
+        ));
+    };
+    // The patterns are worth having even if the templates can't be ranked
+    let templates = match gitignore_api::get_template_bodies().await {
+        Ok(templates) => templates,
+        Err(error) => {
+            eprintln!(
+                "[{}] Warning: Couldn't get the templates to rank them: {}",
+                env!("CARGO_PKG_NAME"),
+                error
             );
-            Err(color_eyre::eyre::Report::new(error))
+            vec![]
+        }
+    };
+    let suggestions = suggest::suggest(&repository, &rules_dir, &templates)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&suggestions)?);
+        Ok(())
+    } else if suggestions.is_empty() {
+        println!(
+            "[{}] Every untracked file is ignored already.",
+            env!("CARGO_PKG_NAME")
+        );
+        Ok(())
+    } else {
+        run_tui(interactive_args, Some(suggestions)).await
+    }
+}
+
+async fn search(args: SearchArgs) -> color_eyre::eyre::Result<()> {
+    let templates = gitignore_api::get_template_bodies().await?;
+    let found = search::search(
+        templates.iter().map(|(name, body)| (name, body)),
+        &args.pattern,
+    );
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&found)?);
+    } else if found.is_empty() {
+        println!(r#"No templates contain "{}""#, args.pattern);
+    } else {
+        for template in &found {
+            println!("{}", template.name);
+            for line in &template.lines {
+                println!("  {}: {}", line.number, line.text);
+            }
+        }
+    }
+    Ok(())
+}
+
+// The rules to check paths against, from a file or the templates on the command line, along with
+// the file they'd be in and where to say they came from.
+async fn read_rules(args: &RulesSourceArgs) -> color_eyre::eyre::Result<(PathBuf, String, String)> {
+    let file = target::resolve(args.file.as_deref(), args.here).path;
+    if args.templates.is_empty() {
+        match std::fs::read_to_string(&file) {
+            Ok(content) => {
+                let absolute = util::absolute_path(&file);
+                let source = std::env::current_dir()
+                    .ok()
+                    .and_then(|current_dir| util::relative_path(&current_dir, &absolute))
+                    .unwrap_or(absolute.display().to_string());
+                Ok((file, content, source))
+            }
+            Err(error) => {
+                eprint!("Problem reading {}: ", file.display());
+                Err(color_eyre::eyre::Report::new(error))
+            }
+        }
+    } else {
+        // Line numbers are those of `gi-gen generate` output
+        let assembled = fetch_gitignore(&args.templates, Layout::default(), false).await?;
+        Ok((file, assembled.content, "generated".to_string()))
+    }
+}
+
+// Like `.gitignore:12 "*.log" (Node)`
+fn describe_rule(gitignore: &Gitignore, source: &str, number: usize) -> String {
+    let text = gitignore.lines()[number - 1].text.trim_end();
+    match gitignore.section_of(number) {
+        Some(section) => format!(r#"{}:{} "{}" ({})"#, source, number, text, section),
+        None => format!(r#"{}:{} "{}""#, source, number, text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Ready;
+    use std::path::PathBuf;
+
+    use super::generate_locked_in;
+    use crate::lock::{self, Lock};
+    use crate::testing::TempDir;
+
+    type Response = (String, Result<String, std::io::Error>);
+
+    // Templates as the API would return them now. Any other template can't be fetched.
+    fn upstream(templates: &[(&str, &str)]) -> impl FnOnce(Vec<String>) -> Ready<Vec<Response>> {
+        let templates: Vec<(String, String)> = templates
+            .iter()
+            .map(|(name, body)| (name.to_string(), body.to_string()))
+            .collect();
+        move |names| {
+            let responses = names
+                .into_iter()
+                .map(|name| {
+                    let body = templates
+                        .iter()
+                        .find(|(template, _)| *template == name)
+                        .map(|(_, body)| body.clone())
+                        .ok_or_else(|| std::io::Error::other(format!("no {} template", name)));
+                    (name, body)
+                })
+                .collect();
+            std::future::ready(responses)
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    // A directory with a lock file and a cache of template content
+    struct Locked(TempDir);
+
+    impl Locked {
+        fn new() -> Self {
+            Self(TempDir::new())
+        }
+        fn lock_path(&self) -> PathBuf {
+            self.0.path().join(lock::LOCK_FILE_NAME)
         }
+        fn cache_dir(&self) -> PathBuf {
+            self.0.path().join("templates")
+        }
+        fn generate(
+            &self,
+            template_names: &[&str],
+            now: &[(&str, &str)],
+        ) -> Result<String, String> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap();
+            runtime
+                .block_on(generate_locked_in(
+                    &names(template_names),
+                    &self.lock_path(),
+                    &self.cache_dir(),
+                    upstream(now),
+                ))
+                .map(|assembled| assembled.content)
+                .map_err(|error| error.to_string())
+        }
+        fn lock(&self) -> Lock {
+            Lock::read(&self.lock_path()).unwrap().unwrap()
+        }
+    }
+
+    const RUST: &str = "### Rust ###\n/target\n";
+    const RUST_NOW: &str = "### Rust ###\n/target\n*.pdb\n";
+    const NODE: &str = "### Node ###\nnode_modules/\n";
+
+    #[test]
+    fn pins_templates_to_their_cached_content() {
+        let locked = Locked::new();
+        let content = locked.generate(&["rust"], &[("rust", RUST)]).unwrap();
+        assert!(content.contains("/target"));
+        let pinned = locked.lock().templates;
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].sha256, lock::sha256(RUST.trim_end()));
+
+        // The template has changed upstream, but it's not fetched again
+        let content = locked.generate(&[], &[("rust", RUST_NOW)]).unwrap();
+        assert!(!content.contains("*.pdb"));
+        assert_eq!(locked.lock().templates, pinned);
+    }
+
+    #[test]
+    fn checks_cached_content_by_its_hash() {
+        let locked = Locked::new();
+        locked.generate(&["rust"], &[("rust", RUST)]).unwrap();
+        let hash = locked.lock().templates[0].sha256.clone();
+        std::fs::write(locked.cache_dir().join(&hash), "*\n").unwrap();
+
+        // The cached content doesn't match its hash, so the template is fetched again
+        let content = locked.generate(&["rust"], &[("rust", RUST)]).unwrap();
+        assert!(content.contains("/target"));
+        assert!(!content.contains("*\n"));
+        assert!(lock::cached(&locked.cache_dir(), &hash).is_some());
+    }
+
+    #[test]
+    fn fails_when_a_pinned_template_changed_and_isnt_cached() {
+        let locked = Locked::new();
+        locked.generate(&["rust"], &[("rust", RUST)]).unwrap();
+        std::fs::remove_dir_all(locked.cache_dir()).unwrap();
+        let lock_text = std::fs::read_to_string(locked.lock_path()).unwrap();
+
+        let error = locked
+            .generate(&["rust"], &[("rust", RUST_NOW)])
+            .unwrap_err();
+        assert!(error.contains("rust template has changed"), "{}", error);
+        // The lock still pins the old content
+        assert_eq!(
+            std::fs::read_to_string(locked.lock_path()).unwrap(),
+            lock_text
+        );
+    }
+
+    #[test]
+    fn only_rewrites_the_lock_when_it_changes() {
+        let locked = Locked::new();
+        locked.generate(&["rust"], &[("rust", RUST)]).unwrap();
+        // The same lock written another way is left alone
+        let compact = serde_json::to_string(&locked.lock()).unwrap();
+        std::fs::write(locked.lock_path(), &compact).unwrap();
+        locked.generate(&["rust"], &[]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(locked.lock_path()).unwrap(),
+            compact
+        );
+
+        // Adding a template changes it
+        locked
+            .generate(&["rust", "node"], &[("node", NODE)])
+            .unwrap();
+        let pinned: Vec<String> = locked
+            .lock()
+            .templates
+            .into_iter()
+            .map(|template| template.name)
+            .collect();
+        assert_eq!(pinned, ["rust", "node"]);
     }
 }
@@ -0,0 +1,233 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-05
+ */
+
+use std::path::Path;
+
+use crate::gitignore::matcher::Matcher;
+use crate::gitignore::Gitignore;
+
+// How many files and directories to look at, so a huge tree doesn't hold up the app
+const WALK_LIMIT: usize = 20_000;
+
+/// A file or directory in a tree
+#[derive(Clone, Debug)]
+pub(crate) struct Entry {
+    /// Relative to the top of the tree, with "/" between components
+    pub(crate) path: String,
+    pub(crate) name: String,
+    pub(crate) depth: usize,
+    pub(crate) is_dir: bool,
+    /// 1 for a file, or how many files a directory has at any depth
+    pub(crate) files: usize,
+    /// The disk space a file, or all the files in a directory, take
+    pub(crate) bytes: u64,
+    /// Of those files, how many the existing rules don't ignore, and the space they take
+    pub(crate) unignored_files: usize,
+    pub(crate) unignored_bytes: u64,
+    /// Whether the existing rules ignore it. What's in an ignored directory isn't looked at.
+    pub(crate) ignored: bool,
+}
+
+/// The files and directories under a directory, in the order a tree view shows them
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Tree {
+    pub(crate) entries: Vec<Entry>,
+    /// Whether there was more than could be looked at
+    pub(crate) truncated: bool,
+}
+
+/// An entry to show, and whether the rules being previewed ignore it
+pub(crate) struct Row {
+    /// Of the entry in the tree
+    pub(crate) index: usize,
+    pub(crate) ignored: bool,
+    /// Ignored by the rules being previewed but not by the existing ones
+    pub(crate) newly: bool,
+}
+
+/// What a set of rules would ignore in a tree, compared with the existing rules
+#[derive(Default)]
+pub(crate) struct Preview {
+    /// What's in an ignored directory isn't shown
+    pub(crate) rows: Vec<Row>,
+    /// How many files are newly ignored, and the disk space they take
+    pub(crate) files: usize,
+    pub(crate) bytes: u64,
+}
+
+impl Tree {
+    /// Looks at everything under `root` except .git, checking it against `existing` rules that
+    /// apply to `root`. Directories come before files, each in order by name.
+    pub(crate) fn walk(root: &Path, existing: &str) -> Self {
+        let gitignore = Gitignore::parse(existing);
+        let matcher = Matcher::new(&gitignore);
+        let mut tree = Tree::default();
+        tree.walk_dir(root, "", 0, &matcher);
+        tree
+    }
+    /// Checks the tree against `content`, the rules that would replace the existing ones.
+    pub(crate) fn preview(&self, content: &str) -> Preview {
+        let gitignore = Gitignore::parse(content);
+        let matcher = Matcher::new(&gitignore);
+        let mut preview = Preview::default();
+        // The depth of an ignored directory whose entries aren't shown
+        let mut collapsed: Option<usize> = None;
+        for (index, entry) in self.entries.iter().enumerate() {
+            match collapsed {
+                Some(depth) if entry.depth > depth => continue,
+                _ => collapsed = None,
+            }
+            let ignored = matcher
+                .check(&entry.path, entry.is_dir)
+                .is_some_and(|found| found.ignored());
+            let newly = ignored && !entry.ignored;
+            // What the existing rules ignore in a newly ignored directory was ignored already
+            if newly {
+                preview.files += entry.unignored_files;
+                preview.bytes += entry.unignored_bytes;
+            }
+            if entry.is_dir && (ignored || entry.ignored) {
+                collapsed = Some(entry.depth);
+            }
+            preview.rows.push(Row {
+                index,
+                ignored,
+                newly,
+            });
+        }
+        preview
+    }
+}
+impl Tree {
+    // Adds what's in a directory, returning how many files it has and the space they take, and
+    // the same for just the files the existing rules don't ignore.
+    fn walk_dir(
+        &mut self,
+        dir: &Path,
+        prefix: &str,
+        depth: usize,
+        matcher: &Matcher,
+    ) -> ((usize, u64), (usize, u64)) {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return ((0, 0), (0, 0));
+        };
+        let mut children: Vec<(String, bool, u64)> = read_dir
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                // A symbolic link is a file to git, wherever it points
+                let metadata = entry.path().symlink_metadata().ok()?;
+                (name != ".git").then_some((name, metadata.is_dir(), metadata.len()))
+            })
+            .collect();
+        children.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let (mut files, mut bytes) = (0, 0);
+        let (mut unignored_files, mut unignored_bytes) = (0, 0);
+        for (name, is_dir, len) in children {
+            if self.entries.len() >= WALK_LIMIT {
+                self.truncated = true;
+                break;
+            }
+            let path = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+            let ignored = matcher
+                .check(&path, is_dir)
+                .is_some_and(|found| found.ignored());
+            let index = self.entries.len();
+            self.entries.push(Entry {
+                path,
+                name,
+                depth,
+                is_dir,
+                files: 1,
+                bytes: len,
+                unignored_files: if ignored { 0 } else { 1 },
+                unignored_bytes: if ignored { 0 } else { len },
+                ignored,
+            });
+            if is_dir {
+                let ((dir_files, dir_bytes), (dir_unignored_files, dir_unignored_bytes)) =
+                    if ignored {
+                        ((0, 0), (0, 0))
+                    } else {
+                        let path = self.entries[index].path.clone();
+                        self.walk_dir(
+                            &dir.join(&self.entries[index].name),
+                            &path,
+                            depth + 1,
+                            matcher,
+                        )
+                    };
+                let entry = &mut self.entries[index];
+                entry.files = dir_files;
+                entry.bytes = dir_bytes;
+                entry.unignored_files = dir_unignored_files;
+                entry.unignored_bytes = dir_unignored_bytes;
+            }
+            let entry = &self.entries[index];
+            files += entry.files;
+            bytes += entry.bytes;
+            unignored_files += entry.unignored_files;
+            unignored_bytes += entry.unignored_bytes;
+        }
+        ((files, bytes), (unignored_files, unignored_bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tree;
+    use crate::testing::TempDir;
+
+    // A tree with a file and a directory that has a log file and another file in it
+    fn walk(existing: &str) -> (TempDir, Tree) {
+        let dir = TempDir::new();
+        dir.write("build/out.o", "12345678");
+        dir.write("build/debug.log", "1234");
+        dir.write("main.rs", "12");
+        let tree = Tree::walk(dir.path(), existing);
+        (dir, tree)
+    }
+
+    #[test]
+    fn counts_what_a_directory_has() {
+        let (_dir, tree) = walk("*.log\n");
+        let names: Vec<&str> = tree
+            .entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["build", "build/debug.log", "build/out.o", "main.rs"]
+        );
+        let build = &tree.entries[0];
+        assert_eq!((build.files, build.bytes), (2, 12));
+        assert_eq!((build.unignored_files, build.unignored_bytes), (1, 8));
+        assert!(tree.entries[1].ignored);
+    }
+
+    #[test]
+    fn only_counts_what_is_newly_ignored() {
+        let (_dir, tree) = walk("*.log\n");
+        let preview = tree.preview("*.log\nbuild/\n");
+        // The log file in build/ was ignored already
+        assert_eq!((preview.files, preview.bytes), (1, 8));
+        let rows: Vec<(usize, bool, bool)> = preview
+            .rows
+            .iter()
+            .map(|row| (row.index, row.ignored, row.newly))
+            .collect();
+        assert_eq!(rows, [(0, true, true), (3, false, false)]);
+
+        let (_dir, tree) = walk("");
+        let preview = tree.preview("build/\n*.rs\n");
+        assert_eq!((preview.files, preview.bytes), (3, 14));
+        assert_eq!(tree.preview("").files, 0);
+    }
+}
@@ -0,0 +1,65 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-10
+ */
+
+use crate::gitignore::matcher;
+use crate::gitignore::Gitignore;
+
+/// A line of a template that has what's searched for
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct FoundLine {
+    /// Starting at 1
+    pub(crate) number: usize,
+    pub(crate) text: String,
+}
+
+/// A template with lines that have what's searched for
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct Found {
+    pub(crate) name: String,
+    pub(crate) lines: Vec<FoundLine>,
+}
+
+/// Looks through the templates' rules and comments, not their names, for lines that contain
+/// `query`, ignoring case, or rules that would ignore it as a path, so "*.pyc" finds "*.py[cod]".
+/// A query ending in "/" is a directory. Templates come back in order by name, and none come back
+/// for an empty query.
+pub(crate) fn search<'a>(
+    templates: impl IntoIterator<Item = (&'a String, &'a String)>,
+    query: &str,
+) -> Vec<Found> {
+    let query = query.trim();
+    let lowercase = query.to_lowercase();
+    let is_dir = query.ends_with('/');
+    let path = query.trim_start_matches('/').trim_end_matches('/');
+    if path.is_empty() {
+        return vec![];
+    }
+    let mut found: Vec<Found> = templates
+        .into_iter()
+        .filter_map(|(name, body)| {
+            let gitignore = Gitignore::parse(body);
+            let lines: Vec<FoundLine> = gitignore
+                .lines()
+                .iter()
+                .filter(|line| {
+                    line.text.to_lowercase().contains(&lowercase)
+                        || line
+                            .rule()
+                            .is_some_and(|rule| matcher::rule_matches(rule, path, is_dir))
+                })
+                .map(|line| FoundLine {
+                    number: line.number,
+                    text: line.text.trim_end().to_string(),
+                })
+                .collect();
+            (!lines.is_empty()).then(|| Found {
+                name: name.clone(),
+                lines,
+            })
+        })
+        .collect();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    found
+}
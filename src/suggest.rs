@@ -0,0 +1,230 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-03
+ */
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::git::Repository;
+use crate::gitignore::generated::Assembled;
+use crate::gitignore::matcher::Matcher;
+use crate::gitignore::Gitignore;
+use crate::util;
+
+/// The section the patterns picked from the suggestions go in
+pub(crate) const SECTION: &str = "gi-gen suggest";
+
+/// A pattern for some of the untracked files
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct Suggestion {
+    pub(crate) pattern: String,
+    /// The untracked files it matches, with a directory that has nothing tracked in it ending in
+    /// "/"
+    pub(crate) paths: Vec<String>,
+}
+
+/// A template with rules that match some of the untracked files
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct TemplateMatch {
+    pub(crate) name: String,
+    pub(crate) paths: Vec<String>,
+    /// How many tracked files its rules match too, which git keeps tracking anyway
+    pub(crate) tracked: usize,
+}
+
+/// Ways to ignore the untracked files that nothing ignores yet, most useful first
+#[derive(Debug, Default, serde::Serialize)]
+pub(crate) struct Suggestions {
+    pub(crate) templates: Vec<TemplateMatch>,
+    pub(crate) patterns: Vec<Suggestion>,
+}
+
+impl Suggestions {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.templates.is_empty() && self.patterns.is_empty()
+    }
+}
+
+/// Finds the untracked files under `rules_dir` that no ignore rule covers yet, and suggests
+/// patterns and templates for a file of ignore rules there. A pattern is as general as it can be
+/// without matching a tracked file.
+pub(crate) fn suggest(
+    repository: &Repository,
+    rules_dir: &Path,
+    templates: &[(String, String)],
+) -> std::io::Result<Suggestions> {
+    let Some(prefix) = util::relative_path(repository.work_tree(), rules_dir) else {
+        return Ok(Suggestions::default());
+    };
+    // Paths relative to the rules directory, leaving out anything outside it
+    let relative = |paths: Vec<String>| -> Vec<String> {
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                if prefix.is_empty() {
+                    Some(path)
+                } else {
+                    let relative = path.strip_prefix(prefix.as_str())?.strip_prefix('/')?;
+                    Some(relative.to_string())
+                }
+            })
+            .collect()
+    };
+    let untracked = relative(repository.untracked_files()?);
+    let tracked = relative(repository.tracked_files()?);
+    Ok(Suggestions {
+        templates: rank(&untracked, &tracked, templates),
+        patterns: generalize(&untracked, &tracked),
+    })
+}
+
+/// Adds patterns after the generated rules, in a section of their own that `gi-gen check` leaves
+/// alone.
+pub(crate) fn append_patterns(assembled: &mut Assembled, patterns: &[String]) {
+    if patterns.is_empty() {
+        return;
+    }
+    if !assembled.content.is_empty() {
+        assembled.content.push_str("\n\n");
+    }
+    let start = assembled.content.matches('\n').count();
+    assembled.content.push_str(&format!("### {} ###", SECTION));
+    for pattern in patterns {
+        assembled.content.push('\n');
+        assembled.content.push_str(pattern);
+    }
+    let end = start + 1 + patterns.len();
+    assembled.templates.push((SECTION.to_string(), start..end));
+}
+
+// Groups the untracked paths by the most general pattern that matches them but no tracked file:
+// a directory's name, a file's extension, a file's name, or else the path itself.
+fn generalize(untracked: &[String], tracked: &[String]) -> Vec<Suggestion> {
+    let mut safe: HashMap<String, bool> = HashMap::new();
+    let mut suggestions: Vec<Suggestion> = vec![];
+    for path in untracked {
+        let pattern = candidates(path)
+            .into_iter()
+            .find(|pattern| {
+                *safe
+                    .entry(pattern.clone())
+                    .or_insert_with(|| matches_none(pattern, tracked))
+            })
+            .unwrap_or_else(|| anchored(path));
+        match suggestions
+            .iter_mut()
+            .find(|suggestion| suggestion.pattern == pattern)
+        {
+            Some(suggestion) => suggestion.paths.push(path.clone()),
+            None => suggestions.push(Suggestion {
+                pattern,
+                paths: vec![path.clone()],
+            }),
+        }
+    }
+    suggestions.sort_by(|a, b| {
+        b.paths
+            .len()
+            .cmp(&a.paths.len())
+            .then_with(|| a.pattern.cmp(&b.pattern))
+    });
+    suggestions
+}
+
+// The patterns that could cover a path, most general first, not counting the path itself
+fn candidates(path: &str) -> Vec<String> {
+    let is_dir = path.ends_with('/');
+    let path = path.trim_end_matches('/');
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let mut candidates = vec![];
+    if is_dir {
+        candidates.push(format!("{}/", escape(name)));
+    } else {
+        // A name like ".env" is all name and no extension
+        if let Some((stem, extension)) = name.rsplit_once('.') {
+            if !stem.is_empty() && !extension.is_empty() {
+                candidates.push(format!("*.{}", escape(extension)));
+            }
+        }
+        candidates.push(escape(name));
+    }
+    candidates
+}
+
+// A pattern that matches only this path
+fn anchored(path: &str) -> String {
+    let is_dir = path.ends_with('/');
+    let components: Vec<String> = path.trim_end_matches('/').split('/').map(escape).collect();
+    format!("/{}{}", components.join("/"), if is_dir { "/" } else { "" })
+}
+
+// Whether a pattern leaves all the tracked files alone
+fn matches_none(pattern: &str, tracked: &[String]) -> bool {
+    let gitignore = Gitignore::parse(pattern);
+    let matcher = Matcher::new(&gitignore);
+    !tracked.iter().any(|path| {
+        matcher
+            .check(path, false)
+            .is_some_and(|found| found.ignored())
+    })
+}
+
+// A file or directory name as a pattern that matches just that name
+fn escape(name: &str) -> String {
+    let mut escaped = String::new();
+    for (index, ch) in name.char_indices() {
+        let special = matches!(ch, '*' | '?' | '[' | '\\')
+            || (index == 0 && matches!(ch, '#' | '!'))
+            // Trailing spaces are dropped unless they're escaped
+            || (ch == ' ' && name[index..].trim_end_matches(' ').is_empty());
+        if special {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+// Ranks templates by how many of the untracked paths they'd ignore, leaving out those that
+// wouldn't ignore any. Matching fewer tracked files breaks a tie.
+fn rank(
+    untracked: &[String],
+    tracked: &[String],
+    templates: &[(String, String)],
+) -> Vec<TemplateMatch> {
+    let mut ranked: Vec<TemplateMatch> = templates
+        .iter()
+        .filter_map(|(name, body)| {
+            let gitignore = Gitignore::parse(body);
+            let matcher = Matcher::new(&gitignore);
+            let ignores = |path: &String| {
+                let is_dir = path.ends_with('/');
+                matcher
+                    .check(path.trim_end_matches('/'), is_dir)
+                    .is_some_and(|found| found.ignored())
+            };
+            let paths: Vec<String> = untracked
+                .iter()
+                .filter(|path| ignores(path))
+                .cloned()
+                .collect();
+            if paths.is_empty() {
+                return None;
+            }
+            Some(TemplateMatch {
+                name: name.clone(),
+                paths,
+                tracked: tracked.iter().filter(|path| ignores(path)).count(),
+            })
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.paths
+            .len()
+            .cmp(&a.paths.len())
+            .then(a.tracked.cmp(&b.tracked))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    ranked
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-06-12
+ */
+
+// Helpers the tests share
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A small xorshift generator, so a failure can be run again from the same seed
+pub(crate) struct Random(pub(crate) u64);
+
+impl Random {
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+    /// Between 1 and `most` lines, each picked from `pool`
+    pub(crate) fn lines(&mut self, pool: &[&str], most: usize) -> String {
+        let count = 1 + self.below(most);
+        (0..count)
+            .map(|_| pool[self.below(pool.len())])
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+}
+
+/// Every path made of one of `names` under up to `depth` of `dirs`, like "a/b/x.log" for a
+/// depth of 2
+pub(crate) fn paths(dirs: &[&str], names: &[&str], depth: usize) -> Vec<String> {
+    let mut prefixes = vec![String::new()];
+    let mut level = vec![String::new()];
+    for _ in 0..depth {
+        level = level
+            .iter()
+            .flat_map(|prefix| dirs.iter().map(move |dir| format!("{}{}/", prefix, dir)))
+            .collect();
+        prefixes.extend(level.iter().cloned());
+    }
+    prefixes
+        .iter()
+        .flat_map(|prefix| names.iter().map(move |name| format!("{}{}", prefix, name)))
+        .collect()
+}
+
+/// A directory of its own under the system's temporary directory, removed when it's dropped
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "{}-test-{}-{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        // The temporary directory may be behind a symbolic link, as on macOS
+        Self(path.canonicalize().unwrap())
+    }
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+    /// Writes a file under the directory, making any directories it's in
+    pub(crate) fn write(&self, relative: &str, content: &str) -> PathBuf {
+        let path = self.0.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+}
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -3,7 +3,8 @@ use std::ops::{Deref, DerefMut};
 use color_eyre::eyre::Result;
 use crossterm::cursor;
 use crossterm::event::{
-    DisableMouseCapture, EnableMouseCapture, Event as CrosstermEvent, KeyEventKind,
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event as CrosstermEvent, KeyEventKind,
 };
 use crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
 use futures::{FutureExt, StreamExt};
@@ -108,8 +109,8 @@ impl Tui {
                                     CrosstermEvent::FocusGained => {
                                         _event_tx.send(Event::FocusGained).unwrap();
                                     },
-                                    CrosstermEvent::Paste(_) => {
-                                        /* ignored by this app */
+                                    CrosstermEvent::Paste(text) => {
+                                        _event_tx.send(Event::Paste(text)).unwrap();
                                     },
                                 }
                             }
@@ -132,7 +133,12 @@ impl Tui {
 
     pub fn enter(&mut self) -> Result<()> {
         crossterm::terminal::enable_raw_mode()?;
-        crossterm::execute!(std::io::stderr(), EnterAlternateScreen, cursor::Hide)?;
+        crossterm::execute!(
+            std::io::stderr(),
+            EnterAlternateScreen,
+            EnableBracketedPaste,
+            cursor::Hide
+        )?;
 
         if self.mouse {
             crossterm::execute!(std::io::stderr(), EnableMouseCapture)?;
@@ -148,7 +154,12 @@ impl Tui {
             if self.mouse {
                 crossterm::execute!(std::io::stderr(), DisableMouseCapture)?;
             }
-            crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, cursor::Show)?;
+            crossterm::execute!(
+                std::io::stderr(),
+                DisableBracketedPaste,
+                LeaveAlternateScreen,
+                cursor::Show
+            )?;
             crossterm::terminal::disable_raw_mode()?;
         }
         Ok(())
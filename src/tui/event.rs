@@ -3,12 +3,17 @@
  * Created 2024-04-11
  */
 
+use std::path::PathBuf;
+
 use crossterm::event::{KeyEvent, MouseEvent};
 
+use crate::preview::Tree;
+
 #[derive(Clone, Debug)]
 pub enum Event {
     Init,
     TemplateSelect(String),
+    TemplateMove(String, usize),
     // Quit,
     Error,
     // Closed,
@@ -18,5 +23,12 @@ pub enum Event {
     FocusLost,
     Key(KeyEvent),
     Mouse(MouseEvent),
+    Paste(String),
     Resize(u16, u16),
+    /// The tree of files to preview, walked in the background for the file the rules are saved
+    /// to
+    TreeLoaded(PathBuf, Tree),
+    /// Every template's rules, or None if they couldn't be fetched, which the app gets in the
+    /// background
+    RulesLoaded(Option<Vec<(String, String)>>),
 }
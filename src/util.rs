@@ -3,9 +3,13 @@
  * Created 2024-04-12
  */
 
+use std::path::{Component, Path, PathBuf};
+
 use ratatui::prelude::*;
 
 pub fn centered_rect(width: u16, height: u16, rect: Rect) -> Rect {
+    let width = width.min(rect.width);
+    let height = height.min(rect.height);
     let vert_margin = (rect.height - height) / 2;
     let horiz_margin = (rect.width - width) / 2;
     let vert_layout = Layout::default()
@@ -26,3 +30,145 @@ pub fn centered_rect(width: u16, height: u16, rect: Rect) -> Rect {
         ])
         .split(vert_layout[1])[1]
 }
+
+// Splits a list of template names separated by commas, spaces or line breaks, as they might be
+// pasted or typed on the command line.
+pub fn split_template_list(text: &str) -> Vec<String> {
+    text.split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_lowercase())
+        .collect()
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+// Expands a leading "~" to the user's home directory, the way a shell would.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+// Where to write ignore rules for a path that might be a directory rather than a file.
+pub fn output_file(path: &Path, file_name: &str) -> PathBuf {
+    if path.is_dir() {
+        path.join(file_name)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+// Makes a path absolute and removes "." and ".." components without touching the file system,
+// so it works for paths that don't exist.
+pub fn absolute_path(path: &Path) -> PathBuf {
+    let mut absolute = match std::env::current_dir() {
+        Ok(current_dir) if path.is_relative() => current_dir,
+        _ => PathBuf::new(),
+    };
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    absolute
+}
+
+// A path below `base` with "/" between its components, as ignore rules see it.
+pub fn relative_path(base: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    let components: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(components.join("/"))
+}
+
+// Quotes an argument for a POSIX shell, if it needs it.
+pub fn shell_quote(text: &str) -> String {
+    let safe = |ch: char| ch.is_ascii_alphanumeric() || "-_./:=@%+,".contains(ch);
+    if !text.is_empty() && text.chars().all(safe) {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
+// Where gi-gen keeps files of its own, such as backups.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        match std::env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => home_dir().map(|home| home.join(".local").join("share")),
+        }
+    };
+    base.map(|base| base.join(env!("CARGO_PKG_NAME")))
+}
+
+// The current UTC time, formatted so timestamps sort in order, like "20240508T101500.123Z".
+pub fn timestamp() -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = now.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time = seconds % 86400;
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        now.subsec_millis()
+    )
+}
+
+// Converts days since 1970-01-01 to a (year, month, day) date, using Howard Hinnant's algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// A number of bytes the way people read them, like "1.2 MB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["bytes", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
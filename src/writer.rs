@@ -0,0 +1,166 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-10
+ */
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub(crate) mod backup;
+pub(crate) mod target;
+
+/// Line endings, which gi-gen keeps the same as the file it's changing
+#[derive(Copy, Clone, Debug, Default, PartialEq, clap::ValueEnum)]
+pub(crate) enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// Finds the line ending most of the text's lines use, if it has any.
+    pub(crate) fn detect(text: &str) -> Option<Self> {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+        if crlf + lf == 0 {
+            None
+        } else if crlf > lf {
+            Some(LineEnding::Crlf)
+        } else {
+            Some(LineEnding::Lf)
+        }
+    }
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+    /// Rewrites every line ending in the text with this one, and makes sure it ends with exactly
+    /// one.
+    pub(crate) fn normalize(&self, text: &str) -> String {
+        let text = text.trim_end_matches(['\r', '\n']);
+        if text.is_empty() {
+            return String::new();
+        }
+        let mut normalized = text.lines().collect::<Vec<&str>>().join(self.as_str());
+        normalized.push_str(self.as_str());
+        normalized
+    }
+}
+
+/// Adds generated content to the end of a file's content, after a blank line and a comment
+/// saying where it came from, using the line endings the file already has.
+pub(crate) fn append(existing: &str, addition: &str, comment: &str) -> String {
+    let line_ending = LineEnding::detect(existing).unwrap_or_default();
+    let existing = line_ending.normalize(existing);
+    let addition = line_ending.normalize(addition.trim_start_matches(['\r', '\n']));
+    let eol = line_ending.as_str();
+    if existing.is_empty() {
+        format!("# {comment}{eol}{addition}")
+    } else {
+        format!("{existing}{eol}# {comment}{eol}{addition}")
+    }
+}
+
+/// Saves content to a file without ever leaving it half written, backing up what was there first
+/// unless told not to.
+pub(crate) fn save(path: &Path, content: &str, backup: bool) -> std::io::Result<()> {
+    // Write through a symbolic link rather than replacing it
+    let path = if path.is_symlink() {
+        std::fs::canonicalize(path)?
+    } else {
+        path.to_path_buf()
+    };
+    if backup {
+        backup::backup(&path)?;
+    }
+    write_atomic(&path, content)
+}
+
+// Writes the content to a temporary file in the same directory and renames it over the target,
+// so a crash or a full disk leaves either the old file or the new one.
+fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    std::fs::create_dir_all(&dir)?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            std::fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        std::fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append, LineEnding};
+
+    #[test]
+    fn detects_the_line_ending_most_lines_use() {
+        assert_eq!(LineEnding::detect("a\r\nb\r\n"), Some(LineEnding::Crlf));
+        assert_eq!(LineEnding::detect("a\nb\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::detect("a\r\nb\nc\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), Some(LineEnding::Crlf));
+        assert_eq!(LineEnding::detect("a"), None);
+        assert_eq!(LineEnding::detect(""), None);
+    }
+
+    #[test]
+    fn normalizes_line_endings() {
+        assert_eq!(LineEnding::Lf.normalize("a\r\nb\r\n"), "a\nb\n");
+        assert_eq!(LineEnding::Crlf.normalize("a\nb\n"), "a\r\nb\r\n");
+        // A missing final line ending is added
+        assert_eq!(LineEnding::Lf.normalize("a\nb"), "a\nb\n");
+        assert_eq!(LineEnding::Crlf.normalize("a\r\nb"), "a\r\nb\r\n");
+        // Trailing blank lines are dropped, but not blank lines between rules
+        assert_eq!(LineEnding::Lf.normalize("a\n\nb\n\n\n"), "a\n\nb\n");
+        assert_eq!(LineEnding::Crlf.normalize("a\r\n\r\n\r\n"), "a\r\n");
+        assert_eq!(LineEnding::Lf.normalize("\n\r\n"), "");
+    }
+
+    #[test]
+    fn appends_after_a_blank_line_and_a_comment() {
+        assert_eq!(
+            append("/target\n", "*.log\n", "Added"),
+            "/target\n\n# Added\n*.log\n"
+        );
+        assert_eq!(
+            append("/target", "\n\n*.log", "Added"),
+            "/target\n\n# Added\n*.log\n"
+        );
+        assert_eq!(
+            append("/target\n\n\n", "*.log\n\n", "Added"),
+            "/target\n\n# Added\n*.log\n"
+        );
+        assert_eq!(append("", "*.log", "Added"), "# Added\n*.log\n");
+    }
+
+    #[test]
+    fn appends_with_the_existing_line_endings() {
+        assert_eq!(
+            append("/target\r\n.env\r\n", "*.log\n*.tmp\n", "Added"),
+            "/target\r\n.env\r\n\r\n# Added\r\n*.log\r\n*.tmp\r\n"
+        );
+        assert_eq!(
+            append("/target\r\n", "*.log", "Added"),
+            "/target\r\n\r\n# Added\r\n*.log\r\n"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-10
+ */
+
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::util;
+
+/// A copy of a file from before gi-gen saved over it
+pub(crate) struct Backup {
+    path: PathBuf,
+    timestamp: String,
+    // The file didn't exist before it was saved, so undoing the save removes it
+    created: bool,
+}
+
+impl Backup {
+    const CONTENT_EXTENSION: &'static str = "bak";
+    const CREATED_EXTENSION: &'static str = "new";
+    // How many backups of a file are kept. Taking another discards the oldest.
+    const LIMIT: usize = 20;
+
+    pub(crate) fn timestamp(&self) -> &str {
+        self.timestamp.as_str()
+    }
+    pub(crate) fn created(&self) -> bool {
+        self.created
+    }
+}
+
+/// Backs up a file before it's saved. If the file doesn't exist yet, that's recorded instead, so
+/// the save can still be undone. Only the most recent backups of each file are kept.
+pub(crate) fn backup(path: &Path) -> std::io::Result<PathBuf> {
+    backup_in(&backups_root()?, path)
+}
+
+/// Puts back the most recent backup of a file and discards it, so undoing again goes back
+/// another step.
+pub(crate) fn restore(path: &Path) -> std::io::Result<Backup> {
+    restore_in(&backups_root()?, path)
+}
+
+// Backs up a file into its own directory under `root`.
+fn backup_in(root: &Path, path: &Path) -> std::io::Result<PathBuf> {
+    let dir = backup_dir(root, path)?;
+    std::fs::create_dir_all(&dir)?;
+    let timestamp = util::timestamp();
+    let backup_path = if path.exists() {
+        let backup_path = dir.join(format!("{}.{}", timestamp, Backup::CONTENT_EXTENSION));
+        std::fs::copy(path, &backup_path)?;
+        backup_path
+    } else {
+        let backup_path = dir.join(format!("{}.{}", timestamp, Backup::CREATED_EXTENSION));
+        std::fs::write(&backup_path, "")?;
+        backup_path
+    };
+    let backups = backups(&dir)?;
+    for backup in &backups[..backups.len().saturating_sub(Backup::LIMIT)] {
+        std::fs::remove_file(&backup.path)?;
+    }
+    Ok(backup_path)
+}
+
+fn restore_in(root: &Path, path: &Path) -> std::io::Result<Backup> {
+    let backup = backups(&backup_dir(root, path)?)?.pop().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("There are no backups of {}", path.display()),
+        )
+    })?;
+    if backup.created {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    } else {
+        let content = std::fs::read_to_string(&backup.path)?;
+        super::save(path, content.as_str(), false)?;
+    }
+    std::fs::remove_file(&backup.path)?;
+    Ok(backup)
+}
+
+// The backups in a file's backup directory, oldest first
+fn backups(dir: &Path) -> std::io::Result<Vec<Backup>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut backups: Vec<Backup> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let timestamp = path.file_stem()?.to_string_lossy().to_string();
+            let created = match path.extension()?.to_str()? {
+                Backup::CONTENT_EXTENSION => false,
+                Backup::CREATED_EXTENSION => true,
+                _ => return None,
+            };
+            Some(Backup {
+                path,
+                timestamp,
+                created,
+            })
+        })
+        .collect();
+    // The timestamps sort in the order they were taken
+    backups.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    Ok(backups)
+}
+
+fn backups_root() -> std::io::Result<PathBuf> {
+    let data_dir = util::data_dir().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            "Can't find a directory to keep backups in",
+        )
+    })?;
+    Ok(data_dir.join("backups"))
+}
+
+// Each file gets its own backup directory under `root`, named after its absolute path.
+fn backup_dir(root: &Path, path: &Path) -> std::io::Result<PathBuf> {
+    let key: String = absolute(path)?
+        .to_string_lossy()
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '.' || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(root.join(key))
+}
+
+// The file may not exist yet, but its directory should.
+fn absolute(path: &Path) -> std::io::Result<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Ok(path);
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+        _ => std::env::current_dir()?,
+    };
+    Ok(match path.file_name() {
+        Some(file_name) => parent.join(file_name),
+        None => parent,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::{backup_dir, backup_in, restore_in, Backup};
+    use crate::testing::TempDir;
+
+    // Backups are named by the millisecond they're taken
+    fn backup(root: &Path, path: &Path) -> std::path::PathBuf {
+        std::thread::sleep(Duration::from_millis(2));
+        backup_in(root, path).unwrap()
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn restores_what_was_backed_up() {
+        let dir = TempDir::new();
+        let root = dir.path().join("backups");
+        let file = dir.write("work/.gitignore", "*.log\n");
+        let backup_path = backup(&root, &file);
+        assert_eq!(backup_path.extension().unwrap(), Backup::CONTENT_EXTENSION);
+        assert_eq!(read(&backup_path), "*.log\n");
+
+        std::fs::write(&file, "/target\n").unwrap();
+        let restored = restore_in(&root, &file).unwrap();
+        assert!(!restored.created());
+        assert_eq!(read(&file), "*.log\n");
+        // The backup is used up
+        assert!(!backup_path.exists());
+        let error = restore_in(&root, &file).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn undoing_a_new_file_removes_it() {
+        let dir = TempDir::new();
+        let root = dir.path().join("backups");
+        let file = dir.path().join(".gitignore");
+        let marker = backup(&root, &file);
+        assert_eq!(marker.extension().unwrap(), Backup::CREATED_EXTENSION);
+        assert_eq!(read(&marker), "");
+
+        std::fs::write(&file, "*.log\n").unwrap();
+        assert!(restore_in(&root, &file).unwrap().created());
+        assert!(!file.exists());
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn restores_the_latest_backup_first() {
+        let dir = TempDir::new();
+        let root = dir.path().join("backups");
+        let file = dir.path().join(".gitignore");
+        backup(&root, &file);
+        for content in ["one\n", "two\n"] {
+            std::fs::write(&file, content).unwrap();
+            backup(&root, &file);
+        }
+        std::fs::write(&file, "three\n").unwrap();
+
+        restore_in(&root, &file).unwrap();
+        assert_eq!(read(&file), "two\n");
+        restore_in(&root, &file).unwrap();
+        assert_eq!(read(&file), "one\n");
+        assert!(restore_in(&root, &file).unwrap().created());
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn keeps_only_the_latest_backups() {
+        let dir = TempDir::new();
+        let root = dir.path().join("backups");
+        let file = dir.path().join(".gitignore");
+        let backup_dir = backup_dir(&root, &file).unwrap();
+        // Something else in the directory isn't a backup, and is left alone
+        std::fs::create_dir_all(&backup_dir).unwrap();
+        std::fs::write(backup_dir.join("notes.txt"), "").unwrap();
+        for index in 0..Backup::LIMIT + 3 {
+            std::fs::write(&file, format!("{}\n", index)).unwrap();
+            backup(&root, &file);
+        }
+        let count = std::fs::read_dir(&backup_dir).unwrap().count();
+        assert_eq!(count, Backup::LIMIT + 1);
+        for _ in 0..Backup::LIMIT {
+            restore_in(&root, &file).unwrap();
+        }
+        // The oldest three are gone
+        assert_eq!(read(&file), "3\n");
+        assert!(restore_in(&root, &file).is_err());
+        assert!(backup_dir.join("notes.txt").exists());
+    }
+}
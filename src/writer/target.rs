@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) 2024 Paul Sobolik
+ * Created 2024-05-13
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::{git, util};
+
+/// Where to save ignore rules, and anything worth knowing about saving them there
+pub(crate) struct Target {
+    pub(crate) path: PathBuf,
+    pub(crate) warning: Option<String>,
+}
+
+/// Works out where ignore rules go. An explicit output wins; otherwise it's the .gitignore file
+/// at the root of the enclosing repository, or in the current directory when `here` is set or
+/// there's no repository.
+pub(crate) fn resolve(output: Option<&Path>, here: bool) -> Target {
+    resolve_named(output, here, ".gitignore")
+}
+
+/// Like `resolve`, for an ignore file with another name, like .dockerignore
+pub(crate) fn resolve_named(output: Option<&Path>, here: bool, file_name: &str) -> Target {
+    let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let repository = git::Repository::discover(&current_dir);
+    resolve_in(&current_dir, repository, output, here, file_name)
+}
+
+// Like `resolve_named`, with the current directory and the repository it's in given
+fn resolve_in(
+    current_dir: &Path,
+    repository: Option<git::Repository>,
+    output: Option<&Path>,
+    here: bool,
+    file_name: &str,
+) -> Target {
+    let path = match (output, &repository) {
+        (Some(output), _) => util::output_file(output, file_name),
+        (None, Some(repository)) if !here => repository.work_tree().join(file_name),
+        _ => PathBuf::from(".").join(file_name),
+    };
+    let warning = repository.and_then(|repository| nested_warning(&path, current_dir, &repository));
+    Target { path, warning }
+}
+
+// A .gitignore file below the top of the working tree only applies to its own directory, which
+// is easy to do by accident by running gi-gen from a subdirectory.
+fn nested_warning(path: &Path, current_dir: &Path, repository: &git::Repository) -> Option<String> {
+    if path.file_name()? != ".gitignore" {
+        return None;
+    }
+    let dir = match path.parent() {
+        Some(parent) => current_dir.join(parent).canonicalize().ok()?,
+        None => current_dir.canonicalize().ok()?,
+    };
+    if dir != repository.work_tree() && dir.starts_with(repository.work_tree()) {
+        Some(format!(
+            "{} is a nested .gitignore; its rules only apply under {}, not the whole repository at {}.",
+            path.display(),
+            dir.display(),
+            repository.work_tree().display()
+        ))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{resolve_in, Target};
+    use crate::git::Repository;
+    use crate::testing::TempDir;
+
+    // A repository at "repo" with a "src" directory in it
+    fn repository(dir: &TempDir) -> Repository {
+        std::fs::create_dir_all(dir.path().join("repo/.git")).unwrap();
+        std::fs::create_dir_all(dir.path().join("repo/src")).unwrap();
+        let work_tree = dir.path().join("repo");
+        Repository::discover_in(&work_tree, &work_tree, |_| None).unwrap()
+    }
+
+    fn resolve(
+        current_dir: &Path,
+        repository: Option<Repository>,
+        output: Option<&Path>,
+        here: bool,
+    ) -> Target {
+        resolve_in(current_dir, repository, output, here, ".gitignore")
+    }
+
+    #[test]
+    fn defaults_to_the_top_of_the_repository() {
+        let dir = TempDir::new();
+        let src = dir.path().join("repo/src");
+        let target = resolve(&src, Some(repository(&dir)), None, false);
+        assert_eq!(target.path, dir.path().join("repo/.gitignore"));
+        assert!(target.warning.is_none());
+    }
+
+    #[test]
+    fn uses_the_current_directory_outside_a_repository() {
+        let dir = TempDir::new();
+        let target = resolve(dir.path(), None, None, false);
+        assert_eq!(target.path, PathBuf::from("./.gitignore"));
+        assert!(target.warning.is_none());
+    }
+
+    #[test]
+    fn warns_about_a_nested_gitignore() {
+        let dir = TempDir::new();
+        let repo = dir.path().join("repo");
+        let src = repo.join("src");
+
+        let target = resolve(&src, Some(repository(&dir)), None, true);
+        assert_eq!(target.path, PathBuf::from("./.gitignore"));
+        let warning = target.warning.unwrap();
+        assert!(warning.contains(&src.display().to_string()));
+
+        // Not at the top of the tree
+        let target = resolve(&repo, Some(repository(&dir)), None, true);
+        assert!(target.warning.is_none());
+
+        // An output directory is where the file goes
+        let target = resolve(&repo, Some(repository(&dir)), Some(&src), false);
+        assert_eq!(target.path, src.join(".gitignore"));
+        assert!(target.warning.is_some());
+        let output = repo.join("src/custom");
+        let target = resolve(&repo, Some(repository(&dir)), Some(&output), false);
+        assert_eq!(target.path, output);
+        assert!(target.warning.is_none());
+
+        // Outside the repository
+        let output = dir.path().join(".gitignore");
+        let target = resolve(&repo, Some(repository(&dir)), Some(&output), false);
+        assert!(target.warning.is_none());
+    }
+
+    #[test]
+    fn only_warns_about_gitignore_files() {
+        let dir = TempDir::new();
+        let src = dir.path().join("repo/src");
+        let target = resolve_in(&src, Some(repository(&dir)), None, true, ".dockerignore");
+        assert_eq!(target.path, PathBuf::from("./.dockerignore"));
+        assert!(target.warning.is_none());
+    }
+}